@@ -0,0 +1,234 @@
+use super::{Game, GameRound};
+
+// The bag composition we are trying to infer from the observed rounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BagComposition {
+    pub red_cubes: i32,
+    pub green_cubes: i32,
+    pub blue_cubes: i32,
+}
+
+impl BagComposition {
+    pub fn total(&self) -> i32 {
+        self.red_cubes + self.green_cubes + self.blue_cubes
+    }
+
+    // A bag can only produce a round if it holds at least as many cubes of each color as were drawn
+    fn can_produce(&self, round: &GameRound) -> bool {
+        round.red_cubes <= self.red_cubes
+            && round.green_cubes <= self.green_cubes
+            && round.blue_cubes <= self.blue_cubes
+    }
+}
+
+// Prior over the total number of cubes in the bag. Within a total, every split between
+// the colors is considered equally likely.
+#[derive(Debug, Clone)]
+pub struct TotalCubesPrior {
+    weights: Vec<(i32, f64)>,
+}
+
+impl TotalCubesPrior {
+    pub fn uniform(min_total: i32, max_total: i32) -> TotalCubesPrior {
+        TotalCubesPrior::from_weights((min_total..=max_total).map(|total| (total, 1.0)).collect())
+    }
+
+    pub fn from_weights(weights: Vec<(i32, f64)>) -> TotalCubesPrior {
+        TotalCubesPrior { weights }
+    }
+
+    fn max_total(&self) -> i32 {
+        self.weights.iter().map(|(total, _)| *total).max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BagPosterior {
+    // Sorted by probability, most likely composition first
+    pub entries: Vec<(BagComposition, f64)>,
+}
+
+impl BagPosterior {
+    pub fn most_likely(&self) -> Option<&(BagComposition, f64)> {
+        self.entries.first()
+    }
+
+    pub fn expected_cubes(&self) -> (f64, f64, f64) {
+        self.entries.iter().fold((0.0, 0.0, 0.0), |(red, green, blue), (bag, probability)| {
+            (
+                red + bag.red_cubes as f64 * probability,
+                green + bag.green_cubes as f64 * probability,
+                blue + bag.blue_cubes as f64 * probability,
+            )
+        })
+    }
+}
+
+// ln(n!) for every n up to the given maximum, so binomials can be computed in log space
+fn ln_factorials(max: i32) -> Vec<f64> {
+    let mut table = vec![0.0; max.max(0) as usize + 1];
+
+    for n in 2..table.len() {
+        table[n] = table[n - 1] + (n as f64).ln();
+    }
+
+    table
+}
+
+fn ln_binomial(n: i32, k: i32, ln_factorials: &[f64]) -> f64 {
+    ln_factorials[n as usize] - ln_factorials[k as usize] - ln_factorials[(n - k) as usize]
+}
+
+// Each round is drawn without replacement from the full bag (the cubes are put back between rounds),
+// so a single round follows the multivariate hypergeometric distribution and rounds are independent.
+fn ln_likelihood(game: &Game, bag: &BagComposition, ln_factorials: &[f64]) -> Option<f64> {
+    let mut ln_likelihood = 0.0;
+
+    for round in &game.rounds {
+        if !bag.can_produce(round) {
+            return None;
+        }
+
+        let drawn = round.red_cubes + round.green_cubes + round.blue_cubes;
+
+        ln_likelihood += ln_binomial(bag.red_cubes, round.red_cubes, ln_factorials)
+            + ln_binomial(bag.green_cubes, round.green_cubes, ln_factorials)
+            + ln_binomial(bag.blue_cubes, round.blue_cubes, ln_factorials)
+            - ln_binomial(bag.total(), drawn, ln_factorials);
+    }
+
+    Some(ln_likelihood)
+}
+
+// All bag compositions with the given total that can produce every round in the game
+fn candidate_bags(game: &Game, total: i32) -> Vec<BagComposition> {
+    let min_red = game.rounds.iter().map(|round| round.red_cubes).max().unwrap_or(0);
+    let min_green = game.rounds.iter().map(|round| round.green_cubes).max().unwrap_or(0);
+    let min_blue = game.rounds.iter().map(|round| round.blue_cubes).max().unwrap_or(0);
+
+    let mut bags = Vec::new();
+
+    for red_cubes in min_red..=(total - min_green - min_blue) {
+        for green_cubes in min_green..=(total - red_cubes - min_blue) {
+            bags.push(BagComposition {
+                red_cubes,
+                green_cubes,
+                blue_cubes: total - red_cubes - green_cubes,
+            });
+        }
+    }
+
+    bags
+}
+
+pub fn posterior(game: &Game, prior: &TotalCubesPrior) -> BagPosterior {
+    let ln_factorials = ln_factorials(prior.max_total());
+
+    let mut ln_weights = Vec::new();
+
+    for (total, weight) in &prior.weights {
+        if *weight <= 0.0 || *total < 0 {
+            continue;
+        }
+
+        // Every split of the total is equally likely, so spread the weight over all of them
+        let splits = ((total + 1) * (total + 2) / 2) as f64;
+        let ln_prior = weight.ln() - splits.ln();
+
+        for bag in candidate_bags(game, *total) {
+            if let Some(ln_likelihood) = ln_likelihood(game, &bag, &ln_factorials) {
+                ln_weights.push((bag, ln_prior + ln_likelihood));
+            }
+        }
+    }
+
+    // Normalize in log space to avoid underflow on games with many rounds
+    let max_ln_weight = ln_weights.iter().map(|(_, w)| *w).fold(f64::NEG_INFINITY, f64::max);
+    let normalizer: f64 = ln_weights.iter().map(|(_, w)| (w - max_ln_weight).exp()).sum();
+
+    let mut entries = ln_weights
+        .into_iter()
+        .map(|(bag, w)| (bag, (w - max_ln_weight).exp() / normalizer))
+        .collect::<Vec<_>>();
+
+    entries.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    BagPosterior { entries }
+}
+
+// Returns the bag with the highest likelihood of producing the game, along with its log likelihood.
+// Ties are broken in favour of the smallest bag.
+pub fn maximum_likelihood(game: &Game, max_total: i32) -> Option<(BagComposition, f64)> {
+    let ln_factorials = ln_factorials(max_total);
+
+    let mut best: Option<(BagComposition, f64)> = None;
+
+    for total in 0..=max_total {
+        for bag in candidate_bags(game, total) {
+            if let Some(ln_likelihood) = ln_likelihood(game, &bag, &ln_factorials) {
+                if best.is_none_or(|(_, best_ln_likelihood)| ln_likelihood > best_ln_likelihood) {
+                    best = Some((bag, ln_likelihood));
+                }
+            }
+        }
+    }
+
+    best
+}
+
+pub fn posterior_per_game(games: &[Game], prior: &TotalCubesPrior) -> Vec<(i32, BagPosterior)> {
+    games.iter().map(|game| (game.id, posterior(game, prior))).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_round_game(red_cubes: i32, green_cubes: i32, blue_cubes: i32) -> Game {
+        Game {
+            id: 1,
+            rounds: vec![GameRound { red_cubes, green_cubes, blue_cubes }],
+        }
+    }
+
+    #[test]
+    fn test_posterior_sums_to_one() {
+        let game = Game::parse("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap().1;
+        let posterior = posterior(&game, &TotalCubesPrior::uniform(10, 20));
+
+        let total: f64 = posterior.entries.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // Every candidate must be able to produce every round
+        for (bag, _) in &posterior.entries {
+            assert!(bag.red_cubes >= 4 && bag.green_cubes >= 2 && bag.blue_cubes >= 6);
+        }
+    }
+
+    #[test]
+    fn test_posterior_with_exact_total() {
+        // With exactly 2 cubes in the bag and a round that drew one red and one blue, there is only one option
+        let game = single_round_game(1, 0, 1);
+        let posterior = posterior(&game, &TotalCubesPrior::uniform(2, 2));
+
+        assert_eq!(posterior.entries.len(), 1);
+        assert_eq!(posterior.most_likely().unwrap().0, BagComposition { red_cubes: 1, green_cubes: 0, blue_cubes: 1 });
+    }
+
+    #[test]
+    fn test_maximum_likelihood_prefers_drawing_the_whole_bag() {
+        // Drawing the entire bag is certain, so the smallest bag that fits the draw wins
+        let game = single_round_game(2, 1, 0);
+        let (bag, ln_likelihood) = maximum_likelihood(&game, 10).unwrap();
+
+        assert_eq!(bag, BagComposition { red_cubes: 2, green_cubes: 1, blue_cubes: 0 });
+        assert!(ln_likelihood.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_maximum_likelihood_impossible() {
+        let game = single_round_game(5, 0, 0);
+        assert_eq!(maximum_likelihood(&game, 4), None);
+    }
+}
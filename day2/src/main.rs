@@ -7,6 +7,9 @@ use nom::{
     multi::{many0, separated_list1},
 };
 
+mod inference;
+mod query;
+
 const MAX_RED_CUBES: i32 = 12;
 const MAX_GREEN_CUBES: i32 = 13;
 const MAX_BLUE_CUBES: i32 = 14;
@@ -78,10 +81,49 @@ impl Game {
     }
 }
 
+fn run_query(expression: &str, games: &[Game]) {
+    let query = match query::Query::parse(expression) {
+        Ok(query) => query,
+        Err(error) => {
+            eprintln!("{}: {}", error, expression);
+            std::process::exit(1);
+        }
+    };
+
+    let results = query::matching_games(&query, games)
+        .and_then(|matching_games| Ok((query::statistics(&matching_games, games.len())?, matching_games)));
+    let (statistics, matching_games) = match results {
+        Ok(results) => results,
+        Err(error) => {
+            eprintln!("{}: {}", error, expression);
+            std::process::exit(1);
+        }
+    };
+
+    for game in &matching_games {
+        println!("Game {}", game.id);
+    }
+
+    println!("Matched {} of {} games", statistics.matching_games, statistics.total_games);
+    println!("Sum of game IDs: {}", statistics.id_sum);
+    println!("Sum of the power of cubes: {}", statistics.power_sum);
+    println!(
+        "Most cubes seen in a round: {} red, {} green, {} blue",
+        statistics.max_red_cubes, statistics.max_green_cubes, statistics.max_blue_cubes
+    );
+}
+
 fn main() {
     let input = include_str!("input.txt");
     let games = nom::multi::separated_list1(line_ending, Game::parse)(input).unwrap().1;
 
+    // Running with `query "<expression>"` lists the matching games instead of solving the puzzle
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() == 3 && args[1] == "query" {
+        run_query(&args[2], &games);
+        return;
+    }
+
     // Find if any game has more than the maximum number of cubes
     let invalid_games: Vec<&Game> = games.iter().filter(|game| {
         game.rounds.iter().any(|round| {
@@ -128,7 +170,26 @@ fn main() {
 
     let sum_of_power_of_cubes = power_of_cubes.iter().sum::<i32>();
 
-    print!("The sum of the power of cubes is {}", sum_of_power_of_cubes);
+    println!("The sum of the power of cubes is {}", sum_of_power_of_cubes);
+
+    // Bag inference, assuming the bag holds at most as many cubes as the part 1 limits allow
+    let max_total_cubes = MAX_RED_CUBES + MAX_GREEN_CUBES + MAX_BLUE_CUBES;
+    let prior = inference::TotalCubesPrior::uniform(0, max_total_cubes);
+
+    for (game, (game_id, posterior)) in games.iter().zip(inference::posterior_per_game(&games, &prior)) {
+        let (expected_red, expected_green, expected_blue) = posterior.expected_cubes();
+
+        match (posterior.most_likely(), inference::maximum_likelihood(game, max_total_cubes)) {
+            (Some((map_bag, probability)), Some((ml_bag, _))) => println!(
+                "Game {}: most probable bag {} red, {} green, {} blue (p = {:.4}), maximum likelihood bag {} red, {} green, {} blue, expected {:.2} red, {:.2} green, {:.2} blue",
+                game_id,
+                map_bag.red_cubes, map_bag.green_cubes, map_bag.blue_cubes, probability,
+                ml_bag.red_cubes, ml_bag.green_cubes, ml_bag.blue_cubes,
+                expected_red, expected_green, expected_blue
+            ),
+            _ => println!("Game {}: no bag within the prior can produce this game", game_id),
+        }
+    }
 }
 
 
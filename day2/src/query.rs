@@ -0,0 +1,405 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, multispace0, satisfy},
+    combinator::{all_consuming, map, map_res, not, value},
+    multi::many0,
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+
+use super::{Game, GameRound};
+
+// A query could look like this:
+// max(red) > 10 and any(round: blue = 0)
+// not (id < 50 or all(round: total <= 5))
+// Fields (red, green, blue, total) refer to a single round, so they can only be used inside any/all,
+// while aggregates (max, min, sum) and the game properties (id, rounds) can be used anywhere.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Red,
+    Green,
+    Blue,
+    Total,
+}
+
+impl Field {
+    fn get(&self, round: &GameRound) -> Result<i32, &'static str> {
+        match self {
+            Field::Red => Ok(round.red_cubes),
+            Field::Green => Ok(round.green_cubes),
+            Field::Blue => Ok(round.blue_cubes),
+            Field::Total => round.red_cubes
+                .checked_add(round.green_cubes)
+                .and_then(|total| total.checked_add(round.blue_cubes))
+                .ok_or("Total cubes of a round overflowed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Max,
+    Min,
+    Sum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Number(i32),
+    Id,
+    RoundCount,
+    Aggregate(Aggregate, Field),
+    Field(Field),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Any(Box<Query>),
+    All(Box<Query>),
+    Compare(Operand, Comparison, Operand),
+}
+
+fn ws<'a, O>(parser: impl FnMut(&'a str) -> IResult<&'a str, O>) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    delimited(multispace0, parser, multispace0)
+}
+
+// A keyword has to end where the word ends, so e.g. "idx" isn't read as "id" followed by "x"
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag(word), not(satisfy(|c| c.is_alphanumeric() || c == '_')))
+}
+
+fn parse_field(input: &str) -> IResult<&str, Field> {
+    alt((
+        value(Field::Red, keyword("red")),
+        value(Field::Green, keyword("green")),
+        value(Field::Blue, keyword("blue")),
+        value(Field::Total, keyword("total")),
+    ))(input)
+}
+
+fn parse_operand(input: &str) -> IResult<&str, Operand> {
+    let aggregate = alt((
+        value(Aggregate::Max, keyword("max")),
+        value(Aggregate::Min, keyword("min")),
+        value(Aggregate::Sum, keyword("sum")),
+    ));
+
+    ws(alt((
+        map(map_res(digit1, str::parse), Operand::Number),
+        map(
+            tuple((aggregate, ws(char('(')), parse_field, ws(char(')')))),
+            |(aggregate, _, field, _)| Operand::Aggregate(aggregate, field),
+        ),
+        value(Operand::Id, keyword("id")),
+        value(Operand::RoundCount, keyword("rounds")),
+        map(parse_field, Operand::Field),
+    )))(input)
+}
+
+fn parse_comparison(input: &str) -> IResult<&str, Query> {
+    // Two character operators have to be tried before their one character prefixes
+    let operator = alt((
+        value(Comparison::NotEqual, tag("!=")),
+        value(Comparison::LessOrEqual, tag("<=")),
+        value(Comparison::GreaterOrEqual, tag(">=")),
+        value(Comparison::Equal, tag("=")),
+        value(Comparison::Less, tag("<")),
+        value(Comparison::Greater, tag(">")),
+    ));
+
+    let (input, (left, comparison, right)) = tuple((parse_operand, operator, parse_operand))(input)?;
+
+    Ok((input, Query::Compare(left, comparison, right)))
+}
+
+fn parse_quantifier(input: &str) -> IResult<&str, Query> {
+    let (input, quantifier) = ws(alt((keyword("any"), keyword("all"))))(input)?;
+    let (input, _) = tuple((char('('), ws(keyword("round")), char(':')))(input)?;
+    let (input, inner) = parse_or(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    let query = match quantifier {
+        "any" => Query::Any(Box::new(inner)),
+        _ => Query::All(Box::new(inner)),
+    };
+
+    Ok((input, query))
+}
+
+fn parse_term(input: &str) -> IResult<&str, Query> {
+    alt((
+        map(preceded(ws(keyword("not")), parse_term), |query| Query::Not(Box::new(query))),
+        delimited(ws(char('(')), parse_or, ws(char(')'))),
+        parse_quantifier,
+        parse_comparison,
+    ))(input)
+}
+
+fn parse_and(input: &str) -> IResult<&str, Query> {
+    let (input, first) = parse_term(input)?;
+    let (input, rest) = many0(preceded(ws(keyword("and")), parse_term))(input)?;
+
+    Ok((input, rest.into_iter().fold(first, |left, right| Query::And(Box::new(left), Box::new(right)))))
+}
+
+fn parse_or(input: &str) -> IResult<&str, Query> {
+    let (input, first) = parse_and(input)?;
+    let (input, rest) = many0(preceded(ws(keyword("or")), parse_and))(input)?;
+
+    Ok((input, rest.into_iter().fold(first, |left, right| Query::Or(Box::new(left), Box::new(right)))))
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, &'static str> {
+        let (_, query) = all_consuming(parse_or)(input).map_err(|_| "Invalid query")?;
+        query.validate(false)?;
+
+        Ok(query)
+    }
+
+    // Round fields only make sense inside a quantifier, and quantifiers cannot be nested
+    fn validate(&self, in_round: bool) -> Result<(), &'static str> {
+        match self {
+            Query::And(left, right) | Query::Or(left, right) => {
+                left.validate(in_round)?;
+                right.validate(in_round)
+            }
+            Query::Not(query) => query.validate(in_round),
+            Query::Any(query) | Query::All(query) => {
+                if in_round {
+                    return Err("Quantifiers cannot be nested");
+                }
+                query.validate(true)
+            }
+            Query::Compare(left, _, right) => {
+                for operand in [left, right] {
+                    if let Operand::Field(_) = operand {
+                        if !in_round {
+                            return Err("Round fields can only be used inside any(round: ...) or all(round: ...)");
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Fails if a value the query looks at doesn't fit in an i32
+    pub fn matches(&self, game: &Game) -> Result<bool, &'static str> {
+        self.evaluate(game, None)
+    }
+
+    fn evaluate(&self, game: &Game, round: Option<&GameRound>) -> Result<bool, &'static str> {
+        let result = match self {
+            Query::And(left, right) => left.evaluate(game, round)? && right.evaluate(game, round)?,
+            Query::Or(left, right) => left.evaluate(game, round)? || right.evaluate(game, round)?,
+            Query::Not(query) => !query.evaluate(game, round)?,
+            Query::Any(query) => {
+                for round in &game.rounds {
+                    if query.evaluate(game, Some(round))? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Query::All(query) => {
+                for round in &game.rounds {
+                    if !query.evaluate(game, Some(round))? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Query::Compare(left, comparison, right) => {
+                let left = operand_value(left, game, round)?;
+                let right = operand_value(right, game, round)?;
+
+                match comparison {
+                    Comparison::Equal => left == right,
+                    Comparison::NotEqual => left != right,
+                    Comparison::Less => left < right,
+                    Comparison::LessOrEqual => left <= right,
+                    Comparison::Greater => left > right,
+                    Comparison::GreaterOrEqual => left >= right,
+                }
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+fn operand_value(operand: &Operand, game: &Game, round: Option<&GameRound>) -> Result<i32, &'static str> {
+    match operand {
+        Operand::Number(number) => Ok(*number),
+        Operand::Id => Ok(game.id),
+        Operand::RoundCount => i32::try_from(game.rounds.len()).map_err(|_| "Round count overflowed"),
+        Operand::Aggregate(aggregate, field) => {
+            let values = game.rounds.iter().map(|round| field.get(round)).collect::<Result<Vec<_>, _>>()?;
+            match aggregate {
+                Aggregate::Max => Ok(values.into_iter().max().unwrap_or(0)),
+                Aggregate::Min => Ok(values.into_iter().min().unwrap_or(0)),
+                Aggregate::Sum => values.into_iter()
+                    .try_fold(0i32, |sum, value| sum.checked_add(value))
+                    .ok_or("Sum of cubes overflowed"),
+            }
+        }
+        // Validation guarantees that fields are only evaluated inside a quantifier
+        Operand::Field(field) => field.get(round.expect("Round field used outside of a quantifier")),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryStatistics {
+    pub matching_games: usize,
+    pub total_games: usize,
+    pub id_sum: i32,
+    pub power_sum: i32,
+    pub max_red_cubes: i32,
+    pub max_green_cubes: i32,
+    pub max_blue_cubes: i32,
+}
+
+pub fn matching_games<'a>(query: &Query, games: &'a [Game]) -> Result<Vec<&'a Game>, &'static str> {
+    let mut matching_games = Vec::new();
+
+    for game in games {
+        if query.matches(game)? {
+            matching_games.push(game);
+        }
+    }
+
+    Ok(matching_games)
+}
+
+pub fn statistics(matching_games: &[&Game], total_games: usize) -> Result<QueryStatistics, &'static str> {
+    // Single colours never need any arithmetic, so they can't fail
+    let max_cubes = |game: &Game, field: Field| game.rounds.iter().map(|round| field.get(round).unwrap()).max().unwrap_or(0);
+
+    let id_sum = matching_games.iter()
+        .try_fold(0i32, |sum, game| sum.checked_add(game.id))
+        .ok_or("Sum of game IDs overflowed")?;
+    let power_sum = matching_games.iter()
+        .try_fold(0i32, |sum, game| {
+            let power = max_cubes(game, Field::Red)
+                .checked_mul(max_cubes(game, Field::Green))?
+                .checked_mul(max_cubes(game, Field::Blue))?;
+            sum.checked_add(power)
+        })
+        .ok_or("Sum of the power of cubes overflowed")?;
+
+    Ok(QueryStatistics {
+        matching_games: matching_games.len(),
+        total_games,
+        id_sum,
+        power_sum,
+        max_red_cubes: matching_games.iter().map(|game| max_cubes(game, Field::Red)).max().unwrap_or(0),
+        max_green_cubes: matching_games.iter().map(|game| max_cubes(game, Field::Green)).max().unwrap_or(0),
+        max_blue_cubes: matching_games.iter().map(|game| max_cubes(game, Field::Blue)).max().unwrap_or(0),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_games(input: &str) -> Vec<Game> {
+        input.lines().map(|line| Game::parse(line).unwrap().1).collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            Query::parse("max(red) > 10 and any(round: blue = 0)"),
+            Ok(Query::And(
+                Box::new(Query::Compare(Operand::Aggregate(Aggregate::Max, Field::Red), Comparison::Greater, Operand::Number(10))),
+                Box::new(Query::Any(Box::new(Query::Compare(Operand::Field(Field::Blue), Comparison::Equal, Operand::Number(0))))),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_query() {
+        assert!(Query::parse("red > 10").is_err());
+        assert!(Query::parse("any(round: all(round: red > 1))").is_err());
+        assert!(Query::parse("max(red) >").is_err());
+        assert_eq!(Query::parse("max(red) > 99999999999"), Err("Invalid query"));
+    }
+
+    #[test]
+    fn test_parse_keywords_need_word_boundary() {
+        // These used to read as "not id = 3" and "id = 1 or id = 2"
+        assert_eq!(Query::parse("notid = 3"), Err("Invalid query"));
+        assert_eq!(Query::parse("id = 1 orid = 2"), Err("Invalid query"));
+        assert_eq!(Query::parse("idx = 3"), Err("Invalid query"));
+        assert_eq!(Query::parse("any(rounds: red > 1)"), Err("Invalid query"));
+
+        // Punctuation still ends a keyword
+        assert!(Query::parse("not(id = 3)").is_ok());
+        assert!(Query::parse("id = 1 or(id = 2)").is_ok());
+        assert!(Query::parse("max(red)>1").is_ok());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let games = parse_games(
+            "Game 1: 2000000000 red, 2000000000 blue; 1 green\n\
+             Game 2147483647: 1 red, 1 green, 1 blue",
+        );
+
+        assert_eq!(Query::parse("sum(blue) > 0").unwrap().matches(&games[0]), Ok(true));
+        assert_eq!(Query::parse("any(round: total > 0)").unwrap().matches(&games[0]), Err("Total cubes of a round overflowed"));
+        assert_eq!(Query::parse("max(total) > 0").unwrap().matches(&games[0]), Err("Total cubes of a round overflowed"));
+
+        let game = parse_games("Game 1: 2000000000 red; 2000000000 red").remove(0);
+        let query = Query::parse("sum(red) > 0").unwrap();
+        assert_eq!(query.matches(&game), Err("Sum of cubes overflowed"));
+        assert_eq!(matching_games(&query, &[game]), Err("Sum of cubes overflowed"));
+
+        assert_eq!(statistics(&[&games[0]], 2), Err("Sum of the power of cubes overflowed"));
+        assert_eq!(statistics(&[&games[1], &games[1]], 2), Err("Sum of game IDs overflowed"));
+        assert_eq!(statistics(&[&games[1]], 2).map(|statistics| statistics.power_sum), Ok(1));
+    }
+
+    #[test]
+    fn test_matching_games() {
+        let games = parse_games(
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green\n\
+             Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue\n\
+             Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+        );
+
+        let query = Query::parse("max(red) > 10 and any(round: blue = 0)").unwrap();
+        let ids = matching_games(&query, &games).unwrap().iter().map(|game| game.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![3]);
+
+        let query = Query::parse("not (all(round: total < 10) or id = 3)").unwrap();
+        let ids = matching_games(&query, &games).unwrap().iter().map(|game| game.id).collect::<Vec<_>>();
+        assert_eq!(ids, Vec::<i32>::new());
+
+        let query = Query::parse("rounds = 3 and sum(green) >= 4").unwrap();
+        let matches = matching_games(&query, &games).unwrap();
+        assert_eq!(matches.iter().map(|game| game.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let statistics = statistics(&matches, games.len()).unwrap();
+        assert_eq!(statistics.id_sum, 6);
+        assert_eq!(statistics.power_sum, 48 + 12 + 1560);
+        assert_eq!(statistics.max_red_cubes, 20);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use rstar::RTree;
+
+use super::{adjacent_parts, gear_ratio, EnginePart};
+use super::config::SchematicConfig;
+
+// A group of numbers and symbols that are all linked to each other through adjacent parts
+#[derive(Debug, Clone)]
+pub struct Assembly {
+    pub parts: Vec<EnginePart>,
+    pub number_count: usize,
+    pub symbol_count: usize,
+    pub number_total: i64,
+    pub gear_ratio_total: i64,
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl Assembly {
+    pub fn size(&self) -> usize {
+        self.parts.len()
+    }
+
+    pub fn width(&self) -> i32 {
+        self.max_x - self.min_x + 1
+    }
+
+    pub fn height(&self) -> i32 {
+        self.max_y - self.min_y + 1
+    }
+}
+
+// Union-find over the indices of the engine parts
+fn find_root(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+
+    // Point everything on the path straight at the root so later lookups are quick
+    let mut current = index;
+    while parents[current] != root {
+        let next = parents[current];
+        parents[current] = root;
+        current = next;
+    }
+
+    root
+}
+
+// Groups the parts into assemblies. Any two adjacent parts are linked, whether they are numbers or symbols.
+// Parts without any neighbours end up in an assembly of their own.
+pub fn find_assemblies(engine_parts: &[EnginePart], rtree: &RTree<EnginePart>, config: &SchematicConfig) -> Vec<Assembly> {
+    // Parts never overlap, so their top left corner identifies them
+    let indices = engine_parts.iter()
+        .enumerate()
+        .map(|(index, part)| ((part.x, part.y), index))
+        .collect::<HashMap<_, _>>();
+
+    let mut parents = (0..engine_parts.len()).collect::<Vec<_>>();
+
+    for (index, engine_part) in engine_parts.iter().enumerate() {
+        for neighbour in adjacent_parts(engine_part, rtree, config.neighbourhood) {
+            let neighbour_index = indices[&(neighbour.x, neighbour.y)];
+
+            let root = find_root(&mut parents, index);
+            let neighbour_root = find_root(&mut parents, neighbour_index);
+            if root != neighbour_root {
+                parents[neighbour_root] = root;
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<&EnginePart>> = HashMap::new();
+    for (index, engine_part) in engine_parts.iter().enumerate() {
+        let root = find_root(&mut parents, index);
+        groups.entry(root).or_default().push(engine_part);
+    }
+
+    let mut assemblies = groups
+        .into_values()
+        .map(|parts| {
+            let numbers = parts.iter()
+                .filter(|part| part.is_number)
+                .map(|part| part.contents.parse::<i64>().unwrap())
+                .collect::<Vec<_>>();
+
+            Assembly {
+                number_count: numbers.len(),
+                symbol_count: parts.len() - numbers.len(),
+                number_total: numbers.iter().sum(),
+                gear_ratio_total: parts.iter()
+                    .filter_map(|part| gear_ratio(part, rtree, config.neighbourhood, &config.gear_rule))
+                    .sum(),
+                min_x: parts.iter().map(|part| part.x).min().unwrap(),
+                min_y: parts.iter().map(|part| part.y).min().unwrap(),
+                max_x: parts.iter().map(|part| part.x + part.width - 1).max().unwrap(),
+                max_y: parts.iter().map(|part| part.y + part.height - 1).max().unwrap(),
+                parts: parts.into_iter().cloned().collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Read order: top to bottom, then left to right
+    assemblies.sort_by_key(|assembly| (assembly.min_y, assembly.min_x));
+
+    assemblies
+}
+
+pub fn assembly_table(assemblies: &[Assembly]) -> String {
+    let mut table = format!(
+        "{:>4} | {:>5} | {:>7} | {:>7} | {:>12} | {:>12} | {}\n",
+        "#", "parts", "numbers", "symbols", "number total", "gear ratios", "bounding box"
+    );
+    table.push_str(&format!("{}\n", "-".repeat(table.trim_end().len())));
+
+    for (index, assembly) in assemblies.iter().enumerate() {
+        table.push_str(&format!(
+            "{:>4} | {:>5} | {:>7} | {:>7} | {:>12} | {:>12} | ({}, {})-({}, {}) {}x{}\n",
+            index + 1,
+            assembly.size(),
+            assembly.number_count,
+            assembly.symbol_count,
+            assembly.number_total,
+            assembly.gear_ratio_total,
+            assembly.min_x,
+            assembly.min_y,
+            assembly.max_x,
+            assembly.max_y,
+            assembly.width(),
+            assembly.height(),
+        ));
+    }
+
+    table
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_schematic;
+
+    #[test]
+    fn test_find_assemblies() {
+        let config = SchematicConfig::default();
+        let (engine_parts, rtree) = parse_schematic(include_str!("example.txt"), &config.symbols);
+        let assemblies = find_assemblies(&engine_parts, &rtree, &config);
+
+        let summary = assemblies.iter()
+            .map(|assembly| (assembly.size(), assembly.number_total, assembly.gear_ratio_total))
+            .collect::<Vec<_>>();
+
+        assert_eq!(summary, vec![
+            // 467, * and 35
+            (3, 502, 16345),
+            // 114 on its own
+            (1, 114, 0),
+            // 633 and #
+            (2, 633, 0),
+            // 617 and *
+            (2, 617, 0),
+            // + and 592
+            (2, 592, 0),
+            // 58 on its own
+            (1, 58, 0),
+            // 755, * and 598
+            (3, 755 + 598, 451490),
+            // $ and 664, which are two cells away from the gear
+            (2, 664, 0),
+        ]);
+
+        let last = assemblies.last().unwrap();
+        assert_eq!((last.min_x, last.min_y, last.max_x, last.max_y), (1, 8, 3, 9));
+        assert_eq!((last.number_count, last.symbol_count), (1, 1));
+    }
+
+    #[test]
+    fn test_assembly_table() {
+        let config = SchematicConfig::default();
+        let (engine_parts, rtree) = parse_schematic("1*2\n...\n.3.", &config.symbols);
+        let assemblies = find_assemblies(&engine_parts, &rtree, &config);
+        let table = assembly_table(&assemblies);
+
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].ends_with("(0, 0)-(2, 0) 3x1"));
+        assert!(lines[3].ends_with("(1, 2)-(1, 2) 1x1"));
+    }
+}
@@ -0,0 +1,298 @@
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+// Which characters in the schematic count as symbols. Digits are always numbers and dots are always empty space.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolSet {
+    // Anything that isn't a digit, a dot or whitespace
+    AnyNonDigitNonDot,
+    // Only the given characters
+    Custom(Vec<char>),
+}
+
+impl SymbolSet {
+    pub fn custom(symbols: &str) -> SymbolSet {
+        SymbolSet::Custom(symbols.chars().filter(|c| !c.is_ascii_digit() && *c != '.').collect())
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            SymbolSet::AnyNonDigitNonDot => !c.is_ascii_digit() && c != '.' && !c.is_whitespace(),
+            SymbolSet::Custom(symbols) => symbols.contains(&c),
+        }
+    }
+
+    // Regex that matches either a single symbol or a whole number
+    pub fn regex_pattern(&self) -> String {
+        match self {
+            SymbolSet::AnyNonDigitNonDot => r"([^0-9.\s])|([0-9]+)".to_string(),
+            SymbolSet::Custom(symbols) if symbols.is_empty() => r"([0-9]+)".to_string(),
+            SymbolSet::Custom(symbols) => {
+                let escaped = symbols
+                    .iter()
+                    .map(|c| regex::escape(&c.to_string()))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                format!(r"({})|([0-9]+)", escaped)
+            }
+        }
+    }
+}
+
+// Which cells around a part count as adjacent to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbourhood {
+    // Horizontal, vertical and diagonal neighbours
+    EightConnected,
+    // Only horizontal and vertical neighbours
+    FourConnected,
+    // Every cell within N steps in both directions, including diagonals
+    Radius(i32),
+}
+
+impl Neighbourhood {
+    // How far away from a part we have to look for neighbours
+    pub fn reach(&self) -> i32 {
+        match self {
+            Neighbourhood::EightConnected | Neighbourhood::FourConnected => 1,
+            Neighbourhood::Radius(radius) => *radius,
+        }
+    }
+
+    // dx and dy are the number of cells between the closest cells of two parts, so 1 means touching
+    pub fn contains(&self, dx: i32, dy: i32) -> bool {
+        match self {
+            Neighbourhood::EightConnected => dx <= 1 && dy <= 1,
+            Neighbourhood::FourConnected => dx + dy <= 1,
+            Neighbourhood::Radius(radius) => dx <= *radius && dy <= *radius,
+        }
+    }
+}
+
+impl TryFrom<&str> for Neighbourhood {
+    type Error = &'static str;
+
+    // Accepts "8", "4" or "radius:N"
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "8" | "8-connected" => Ok(Neighbourhood::EightConnected),
+            "4" | "4-connected" => Ok(Neighbourhood::FourConnected),
+            _ => {
+                let radius = value
+                    .strip_prefix("radius:")
+                    .ok_or("Invalid neighbourhood")?
+                    .parse::<i32>()
+                    .map_err(|_| "Invalid neighbourhood radius")?;
+
+                if radius < 1 {
+                    return Err("Neighbourhood radius must be at least 1");
+                }
+
+                Ok(Neighbourhood::Radius(radius))
+            }
+        }
+    }
+}
+
+// How the numbers around a gear are combined into its ratio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GearCombine {
+    Product,
+    Sum,
+    Max,
+}
+
+impl GearCombine {
+    pub fn combine(&self, numbers: &[i64]) -> i64 {
+        match self {
+            GearCombine::Product => numbers.iter().product(),
+            GearCombine::Sum => numbers.iter().sum(),
+            GearCombine::Max => numbers.iter().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+impl TryFrom<&str> for GearCombine {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "product" => Ok(GearCombine::Product),
+            "sum" => Ok(GearCombine::Sum),
+            "max" => Ok(GearCombine::Max),
+            _ => Err("Invalid gear combine function"),
+        }
+    }
+}
+
+// What makes a symbol a gear: the symbol itself and how many numbers have to be next to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GearRule {
+    pub symbol: String,
+    pub neighbours: RangeInclusive<usize>,
+    pub combine: GearCombine,
+}
+
+impl Default for GearRule {
+    // The standard rule: a '*' next to exactly two numbers, with the ratio being their product
+    fn default() -> Self {
+        GearRule {
+            symbol: "*".to_string(),
+            neighbours: 2..=2,
+            combine: GearCombine::Product,
+        }
+    }
+}
+
+impl GearRule {
+    // Accepts either an exact count "N" or a range "N-M"
+    fn parse_neighbours(value: &str) -> Result<RangeInclusive<usize>, &'static str> {
+        let parse_count = |count: &str| count.parse::<usize>().map_err(|_| "Invalid gear neighbour count");
+
+        let neighbours = match value.split_once('-') {
+            Some((min, max)) => parse_count(min)?..=parse_count(max)?,
+            None => parse_count(value)?..=parse_count(value)?,
+        };
+
+        // A gear without any numbers next to it would still get a ratio, e.g. the empty product of 1
+        if neighbours.is_empty() || *neighbours.start() == 0 {
+            return Err("Invalid gear neighbour range");
+        }
+
+        Ok(neighbours)
+    }
+
+    // Symbols are always a single character, so anything else could never be a gear
+    fn parse_symbol(value: &str, symbols: &SymbolSet) -> Result<String, &'static str> {
+        let mut chars = value.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(symbol), None) if symbols.contains(symbol) => Ok(value.to_string()),
+            (Some(_), None) => Err("Gear symbol isn't one of the symbols"),
+            _ => Err("Gear symbol must be a single character"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchematicConfig {
+    pub symbols: SymbolSet,
+    pub neighbourhood: Neighbourhood,
+    pub gear_rule: GearRule,
+}
+
+impl Default for SchematicConfig {
+    fn default() -> Self {
+        SchematicConfig {
+            symbols: SymbolSet::AnyNonDigitNonDot,
+            neighbourhood: Neighbourhood::EightConnected,
+            gear_rule: GearRule::default(),
+        }
+    }
+}
+
+impl SchematicConfig {
+    // Reads "--symbols <chars>", "--neighbourhood <8|4|radius:N>", "--gear-symbol <symbol>",
+    // "--gear-neighbours <N|N-M>" and "--gear-combine <product|sum|max>" from the command line arguments
+    pub fn from_args(args: &[String]) -> Result<SchematicConfig, &'static str> {
+        let mut config = SchematicConfig::default();
+        let mut gear_symbol = None;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--symbols" => {
+                    config.symbols = SymbolSet::custom(args.next().ok_or("Missing value for --symbols")?);
+                }
+                "--neighbourhood" => {
+                    config.neighbourhood = Neighbourhood::try_from(args.next().ok_or("Missing value for --neighbourhood")?.as_str())?;
+                }
+                "--gear-symbol" => {
+                    gear_symbol = Some(args.next().ok_or("Missing value for --gear-symbol")?);
+                }
+                "--gear-neighbours" => {
+                    config.gear_rule.neighbours = GearRule::parse_neighbours(args.next().ok_or("Missing value for --gear-neighbours")?)?;
+                }
+                "--gear-combine" => {
+                    config.gear_rule.combine = GearCombine::try_from(args.next().ok_or("Missing value for --gear-combine")?.as_str())?;
+                }
+                _ => return Err("Unknown argument"),
+            }
+        }
+
+        // Checked once every argument is read, as --symbols may come after the gear symbol
+        if let Some(gear_symbol) = gear_symbol {
+            config.gear_rule.symbol = GearRule::parse_symbol(gear_symbol, &config.symbols)?;
+        }
+
+        Ok(config)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_set() {
+        assert_eq!(SymbolSet::custom("*#.5"), SymbolSet::Custom(vec!['*', '#']));
+        assert_eq!(SymbolSet::custom("*$").regex_pattern(), r"(\*|\$)|([0-9]+)");
+    }
+
+    #[test]
+    fn test_neighbourhood_contains() {
+        assert!(Neighbourhood::EightConnected.contains(1, 1));
+        assert!(!Neighbourhood::FourConnected.contains(1, 1));
+        assert!(Neighbourhood::FourConnected.contains(0, 1));
+        assert!(Neighbourhood::Radius(2).contains(2, 1));
+        assert!(!Neighbourhood::Radius(2).contains(3, 0));
+    }
+
+    #[test]
+    fn test_config_from_args() {
+        let args = vec!["--neighbourhood".to_string(), "radius:3".to_string(), "--symbols".to_string(), "*#".to_string()];
+        let config = SchematicConfig::from_args(&args).unwrap();
+
+        assert_eq!(config.neighbourhood, Neighbourhood::Radius(3));
+        assert_eq!(config.symbols, SymbolSet::Custom(vec!['*', '#']));
+
+        assert!(SchematicConfig::from_args(&["--neighbourhood".to_string(), "radius:0".to_string()]).is_err());
+        assert!(SchematicConfig::from_args(&["--bogus".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_gear_rule_from_args() {
+        let args = ["--gear-symbol", "#", "--gear-neighbours", "1-3", "--gear-combine", "sum"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>();
+        let config = SchematicConfig::from_args(&args).unwrap();
+
+        assert_eq!(config.gear_rule, GearRule { symbol: "#".to_string(), neighbours: 1..=3, combine: GearCombine::Sum });
+
+        let invalid_range = ["--gear-neighbours", "3-1"].iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(SchematicConfig::from_args(&invalid_range).is_err());
+
+        assert_eq!(GearRule::parse_neighbours("0"), Err("Invalid gear neighbour range"));
+        assert_eq!(GearRule::parse_neighbours("0-2"), Err("Invalid gear neighbour range"));
+    }
+
+    #[test]
+    fn test_gear_symbol_from_args() {
+        let from_args = |args: &[&str]| SchematicConfig::from_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+
+        assert_eq!(from_args(&["--gear-symbol", "**"]), Err("Gear symbol must be a single character"));
+        assert_eq!(from_args(&["--gear-symbol", ""]), Err("Gear symbol must be a single character"));
+        assert_eq!(from_args(&["--gear-symbol", "5"]), Err("Gear symbol isn't one of the symbols"));
+        assert_eq!(from_args(&["--gear-symbol", "#", "--symbols", "*$"]), Err("Gear symbol isn't one of the symbols"));
+        assert_eq!(from_args(&["--gear-symbol", "$", "--symbols", "*$"]).unwrap().gear_rule.symbol, "$");
+    }
+
+    #[test]
+    fn test_gear_combine() {
+        assert_eq!(GearCombine::Product.combine(&[2, 3, 4]), 24);
+        assert_eq!(GearCombine::Sum.combine(&[2, 3, 4]), 9);
+        assert_eq!(GearCombine::Max.combine(&[2, 3, 4]), 4);
+    }
+}
@@ -0,0 +1,262 @@
+use regex::Regex;
+use rstar::{AABB, RTree};
+
+use super::{adjacent_parts, gear_ratio, part_number_value, tokenize_line, EnginePart};
+use super::config::SchematicConfig;
+
+// A schematic that can be edited one cell at a time. Only the parts around an edit are re-tokenized,
+// and the part 1 and part 2 totals are updated from the parts whose contribution could have changed.
+pub struct EditableSchematic {
+    grid: Vec<Vec<char>>,
+    config: SchematicConfig,
+    line_regex: Regex,
+    rtree: RTree<EnginePart>,
+    part1_total: i64,
+    part2_total: i64,
+}
+
+impl EditableSchematic {
+    pub fn new(input: &str, config: SchematicConfig) -> EditableSchematic {
+        let grid = input.lines().map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+        let line_regex = Regex::new(&config.symbols.regex_pattern()).unwrap();
+
+        let engine_parts = grid.iter()
+            .enumerate()
+            .flat_map(|(y, row)| tokenize_line(&row.iter().collect::<String>(), y as i32, &line_regex))
+            .collect::<Vec<_>>();
+
+        let rtree = RTree::bulk_load(engine_parts);
+
+        let mut schematic = EditableSchematic {
+            grid,
+            config,
+            line_regex,
+            rtree,
+            part1_total: 0,
+            part2_total: 0,
+        };
+
+        let all_parts = schematic.rtree.iter().cloned().collect::<Vec<_>>();
+        let (part1_total, part2_total) = schematic.contributions(&all_parts);
+        schematic.part1_total = part1_total;
+        schematic.part2_total = part2_total;
+
+        schematic
+    }
+
+    pub fn part1_total(&self) -> i64 {
+        self.part1_total
+    }
+
+    pub fn part2_total(&self) -> i64 {
+        self.part2_total
+    }
+
+    // Sum of the part 1 and part 2 contributions of the given parts, as they are in the current tree
+    fn contributions(&self, engine_parts: &[EnginePart]) -> (i64, i64) {
+        let neighbourhood = self.config.neighbourhood;
+        let gear_rule = &self.config.gear_rule;
+
+        engine_parts.iter().fold((0, 0), |(part1, part2), engine_part| {
+            (
+                part1 + part_number_value(engine_part, &self.rtree, neighbourhood).unwrap_or(0) as i64,
+                part2 + gear_ratio(engine_part, &self.rtree, neighbourhood, gear_rule).unwrap_or(0),
+            )
+        })
+    }
+
+    // All parts in the current tree next to any of the given parts, excluding the given parts themselves
+    fn neighbours_of(&self, engine_parts: &[EnginePart], excluded: &[EnginePart]) -> Vec<EnginePart> {
+        let mut neighbours: Vec<EnginePart> = Vec::new();
+
+        for engine_part in engine_parts {
+            for neighbour in adjacent_parts(engine_part, &self.rtree, self.config.neighbourhood) {
+                if !excluded.contains(neighbour) && !neighbours.contains(neighbour) {
+                    neighbours.push(neighbour.clone());
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    pub fn set_cell(&mut self, x: usize, y: usize, value: char) -> Result<(), &'static str> {
+        if value == '\n' || value == '\r' {
+            return Err("Cells cannot contain line breaks");
+        }
+
+        let row = self.grid.get(y).ok_or("Row out of bounds")?;
+        if x >= row.len() {
+            return Err("Column out of bounds");
+        }
+
+        // A single cell can only change the tokens on its own row, and only as far as the digit runs
+        // on either side of it reach. Everything outside that span tokenizes exactly as before.
+        let mut start = x;
+        while start > 0 && row[start - 1].is_ascii_digit() {
+            start -= 1;
+        }
+
+        let mut end = x;
+        while end + 1 < row.len() && row[end + 1].is_ascii_digit() {
+            end += 1;
+        }
+
+        // Envelopes include their far edge, so the query also returns touching parts that the filter drops
+        let (span_start, span_end) = (start as i32, end as i32);
+        let span_envelope = AABB::from_corners([span_start, y as i32], [span_end, y as i32]);
+        let removed = self.rtree
+            .locate_in_envelope_intersecting(&span_envelope)
+            .filter(|part| part.y == y as i32 && part.x <= span_end && part.x + part.width > span_start)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut new_row = row.clone();
+        new_row[x] = value;
+
+        let added = tokenize_line(&new_row[start..=end].iter().collect::<String>(), y as i32, &self.line_regex)
+            .into_iter()
+            .map(|part| EnginePart { x: part.x + span_start, ..part })
+            .collect::<Vec<_>>();
+
+        // Parts that survive the edit but sit next to a removed or added part may change their contribution.
+        // Positions don't move, so the neighbours of the added parts can be found in the tree before the edit.
+        let mut changed_parts = removed.clone();
+        changed_parts.extend(added.iter().cloned());
+        let affected = self.neighbours_of(&changed_parts, &removed);
+
+        let mut before = removed.clone();
+        before.extend(affected.iter().cloned());
+        let (part1_before, part2_before) = self.contributions(&before);
+
+        for part in &removed {
+            self.rtree.remove(part);
+        }
+        for part in &added {
+            self.rtree.insert(part.clone());
+        }
+        self.grid[y] = new_row;
+
+        let mut after = added;
+        after.extend(affected);
+        let (part1_after, part2_after) = self.contributions(&after);
+
+        self.part1_total += part1_after - part1_before;
+        self.part2_total += part2_after - part2_before;
+
+        Ok(())
+    }
+}
+
+// Parses an edit given as "x,y,c", e.g. "4,0,1" to put a 1 in the fifth column of the first row
+pub fn parse_edit(value: &str) -> Result<(usize, usize, char), &'static str> {
+    let parts = value.splitn(3, ',').collect::<Vec<_>>();
+    if parts.len() != 3 {
+        return Err("Edits must look like x,y,c");
+    }
+
+    let x = parts[0].parse::<usize>().map_err(|_| "Invalid edit column")?;
+    let y = parts[1].parse::<usize>().map_err(|_| "Invalid edit row")?;
+
+    let mut chars = parts[2].chars();
+    let value = chars.next().ok_or("Missing edit character")?;
+    if chars.next().is_some() {
+        return Err("Edits can only set a single character");
+    }
+
+    Ok((x, y, value))
+}
+
+impl std::fmt::Display for EditableSchematic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for row in &self.grid {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{part1, part2, parse_schematic};
+
+    // Recompute both parts from scratch to check the incremental totals against
+    fn recompute(schematic: &EditableSchematic) -> (i64, i64) {
+        let config = SchematicConfig::default();
+        let (engine_parts, rtree) = parse_schematic(&schematic.to_string(), &config.symbols);
+
+        (
+            part1(&engine_parts, &rtree, config.neighbourhood) as i64,
+            part2(&engine_parts, &rtree, config.neighbourhood, &config.gear_rule),
+        )
+    }
+
+    #[test]
+    fn test_initial_totals() {
+        let schematic = EditableSchematic::new(include_str!("example.txt"), SchematicConfig::default());
+
+        assert_eq!(schematic.part1_total(), 4361);
+        assert_eq!(schematic.part2_total(), 467835);
+    }
+
+    #[test]
+    fn test_remove_symbol() {
+        let mut schematic = EditableSchematic::new(include_str!("example.txt"), SchematicConfig::default());
+
+        // Removing the first gear drops 467 and 35 from part 1 and their ratio from part 2
+        schematic.set_cell(3, 1, '.').unwrap();
+
+        assert_eq!(schematic.part1_total(), 4361 - 467 - 35);
+        assert_eq!(schematic.part2_total(), 467835 - 467 * 35);
+    }
+
+    #[test]
+    fn test_insert_digit_joins_numbers() {
+        let mut schematic = EditableSchematic::new(include_str!("example.txt"), SchematicConfig::default());
+
+        // "467..114.." becomes "467.1114..", and "467" becomes "4678" which still touches the gear
+        schematic.set_cell(4, 0, '1').unwrap();
+        assert_eq!(schematic.rtree.iter().filter(|part| part.contents == "1114").count(), 1);
+        assert_eq!((schematic.part1_total(), schematic.part2_total()), recompute(&schematic));
+
+        schematic.set_cell(3, 0, '8').unwrap();
+        assert_eq!(schematic.rtree.iter().filter(|part| part.contents == "46781114").count(), 1);
+        assert_eq!((schematic.part1_total(), schematic.part2_total()), recompute(&schematic));
+    }
+
+    #[test]
+    fn test_edit_sequence_matches_recompute() {
+        let mut schematic = EditableSchematic::new(include_str!("example.txt"), SchematicConfig::default());
+
+        let edits = [
+            (5, 1, '*'), (2, 2, '.'), (6, 3, '.'), (4, 4, '9'), (7, 5, '#'),
+            (5, 8, '.'), (8, 9, '*'), (0, 0, '.'), (9, 2, '1'), (3, 8, '7'),
+            (4, 9, '*'), (5, 9, '.'), (6, 6, '*'), (1, 6, '3'),
+        ];
+
+        for (x, y, value) in edits {
+            schematic.set_cell(x, y, value).unwrap();
+            assert_eq!((schematic.part1_total(), schematic.part2_total()), recompute(&schematic), "after setting ({}, {}) to {}", x, y, value);
+        }
+    }
+
+    #[test]
+    fn test_parse_edit() {
+        assert_eq!(parse_edit("4,0,1"), Ok((4, 0, '1')));
+        assert_eq!(parse_edit("1,2,,"), Ok((1, 2, ',')));
+        assert!(parse_edit("1,2").is_err());
+        assert!(parse_edit("1,2,ab").is_err());
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let mut schematic = EditableSchematic::new(include_str!("example.txt"), SchematicConfig::default());
+
+        assert!(schematic.set_cell(10, 0, '1').is_err());
+        assert!(schematic.set_cell(0, 10, '1').is_err());
+        assert_eq!(schematic.to_string(), include_str!("example.txt"));
+    }
+}
@@ -1,7 +1,16 @@
+use std::convert::TryFrom;
+
 use regex::Regex;
 use rstar::{AABB, RTree, RTreeObject};
 
-#[derive(Debug, Clone)]
+mod assemblies;
+mod config;
+mod editor;
+mod renderer;
+
+use config::{GearRule, Neighbourhood, SchematicConfig, SymbolSet};
+
+#[derive(Debug, Clone, PartialEq)]
 struct EnginePart {
     x: i32,
     y: i32,
@@ -16,8 +25,16 @@ impl EnginePart {
         !self.is_number
     }
 
-    fn is_gear(&self) -> bool {
-        self.contents == "*"
+    fn is_gear(&self, gear_rule: &GearRule) -> bool {
+        self.is_symbol() && self.contents == gear_rule.symbol
+    }
+
+    // Number of cells between the closest cells of two parts on each axis, so touching parts are 1 apart
+    fn cell_distance(&self, other: &EnginePart) -> (i32, i32) {
+        let dx = i32::max(0, i32::max(other.x - (self.x + self.width - 1), self.x - (other.x + other.width - 1)));
+        let dy = i32::max(0, i32::max(other.y - (self.y + self.height - 1), self.y - (other.y + other.height - 1)));
+
+        (dx, dy)
     }
 }
 
@@ -29,56 +46,112 @@ impl RTreeObject for EnginePart {
     }
 }
 
-fn part1(engine_parts: &Vec<EnginePart>, rtree: &RTree<EnginePart>) -> i32 {
-    let mut sum = 0;
-
-    for engine_part in engine_parts.iter() {
-        if engine_part.is_number {
-                // Get all intersecting engine parts
-                let intersecting_symbols = rtree
-                    .locate_in_envelope_intersecting(&engine_part.envelope())
-                    .filter(|part| part.is_symbol())
-                    .collect::<Vec<_>>();
-                // If there are symbols, then we have a number
-                if intersecting_symbols.len() > 0 {
-                    sum += engine_part.contents.parse::<i32>().unwrap();
-                }
-        }
+// Find all parts that are neighbours of the given part. The RTree narrows the search down to the parts
+// within reach, and the neighbourhood rule decides which of those actually count.
+fn adjacent_parts<'a>(engine_part: &'a EnginePart, rtree: &'a RTree<EnginePart>, neighbourhood: Neighbourhood) -> impl Iterator<Item = &'a EnginePart> {
+    // The radius comes from the command line, so the envelope saturates at the edges of the i32 range
+    let reach = neighbourhood.reach();
+    let search_envelope = AABB::from_corners(
+        [engine_part.x.saturating_sub(reach), engine_part.y.saturating_sub(reach)],
+        [(engine_part.x + engine_part.width - 1).saturating_add(reach), (engine_part.y + engine_part.height - 1).saturating_add(reach)],
+    );
+
+    rtree
+        .locate_in_envelope_intersecting(&search_envelope)
+        .filter(move |part| {
+            let (dx, dy) = engine_part.cell_distance(part);
+            // Parts never overlap, so a distance of zero on both axes is the part itself
+            (dx, dy) != (0, 0) && neighbourhood.contains(dx, dy)
+        })
+}
+
+// The value a number contributes to part 1, if it is a part number
+fn part_number_value(engine_part: &EnginePart, rtree: &RTree<EnginePart>, neighbourhood: Neighbourhood) -> Option<i32> {
+    if !engine_part.is_number {
+        return None;
     }
 
-    sum
+    // Get all intersecting engine parts
+    let intersecting_symbols = adjacent_parts(engine_part, rtree, neighbourhood)
+        .filter(|part| part.is_symbol())
+        .collect::<Vec<_>>();
+
+    // If there are symbols, then we have a number
+    if !intersecting_symbols.is_empty() {
+        Some(engine_part.contents.parse::<i32>().unwrap())
+    } else {
+        None
+    }
 }
 
-fn part2(engine_parts: &Vec<EnginePart>, rtree: &RTree<EnginePart>) -> i32 {
-    let gear_parts = engine_parts.iter()
-        .filter(|part| part.is_gear())
+// The ratio a symbol contributes to part 2, if it is a gear
+fn gear_ratio(engine_part: &EnginePart, rtree: &RTree<EnginePart>, neighbourhood: Neighbourhood, gear_rule: &GearRule) -> Option<i64> {
+    if !engine_part.is_gear(gear_rule) {
+        return None;
+    }
+
+    let intersecting_numbers = adjacent_parts(engine_part, rtree, neighbourhood)
+        .filter(|part| part.is_number)
         .collect::<Vec<_>>();
-    
-    let mut gear_ratio_sum = 0;
 
-    for gear in gear_parts {
-        let intersecting_numbers = rtree
-            .locate_in_envelope_intersecting(&gear.envelope())
-            .filter(|part| part.is_number)
+    if gear_rule.neighbours.contains(&intersecting_numbers.len()) {
+        let numbers = intersecting_numbers.iter()
+            .map(|part| part.contents.parse::<i64>().unwrap())
             .collect::<Vec<_>>();
 
-        if intersecting_numbers.len() == 2 {
-            let first_number = intersecting_numbers[0].contents.parse::<i32>().unwrap();
-            let second_number = intersecting_numbers[1].contents.parse::<i32>().unwrap();
+        Some(gear_rule.combine.combine(&numbers))
+    } else {
+        None
+    }
+}
 
-            gear_ratio_sum += first_number * second_number;
-        }
+fn part1(engine_parts: &Vec<EnginePart>, rtree: &RTree<EnginePart>, neighbourhood: Neighbourhood) -> i32 {
+    engine_parts.iter()
+        .filter_map(|engine_part| part_number_value(engine_part, rtree, neighbourhood))
+        .sum()
+}
+
+fn part2(engine_parts: &Vec<EnginePart>, rtree: &RTree<EnginePart>, neighbourhood: Neighbourhood, gear_rule: &GearRule) -> i64 {
+    engine_parts.iter()
+        .filter_map(|gear| gear_ratio(gear, rtree, neighbourhood, gear_rule))
+        .sum()
+}
+
+fn tokenize_line(line: &str, y: i32, line_regex: &Regex) -> Vec<EnginePart> {
+    let mut engine_parts = Vec::new();
+
+    for capture in line_regex.captures_iter(line) {
+        // Get the first capture group - this is the symbol or number
+        let capture = capture.get(0).unwrap();
+        // Get the x coordinate of the capture group, which is the start of the capture group in the line.
+        // Count characters rather than bytes so symbols outside of ASCII don't shift the columns
+        let x = line[..capture.start()].chars().count() as i32;
+        let contents = capture.as_str().to_string();
+        let width = contents.chars().count() as i32;
+        let is_number = contents.parse::<i32>().is_ok();
+        let engine_part = EnginePart {
+            x,
+            y,
+            width,
+            height: 1,
+            is_number,
+            contents,
+        };
+        engine_parts.push(engine_part);
     }
 
-    gear_ratio_sum
-} 
+    engine_parts
+}
+
+fn load_parse_input(symbols: &SymbolSet) -> (Vec<EnginePart>, RTree<EnginePart>) {
+    parse_schematic(include_str!("input.txt"), symbols)
+}
 
-fn load_parse_input() -> (Vec<EnginePart>, RTree<EnginePart>) {
-    let input = include_str!("input.txt");
+fn parse_schematic(input: &str, symbols: &SymbolSet) -> (Vec<EnginePart>, RTree<EnginePart>) {
     let lines = input.lines().collect::<Vec<_>>();
     
     // Regex to match symbols and numbers on a line. This lets us parse the input into EngineParts
-    let line_regex = Regex::new(r"([+*%/#@&$%=-])|(\d+)+").unwrap();
+    let line_regex = Regex::new(&symbols.regex_pattern()).unwrap();
 
     let mut engine_parts = Vec::new();
     // y coordinate of the current line - incremented after each line
@@ -86,24 +159,7 @@ fn load_parse_input() -> (Vec<EnginePart>, RTree<EnginePart>) {
 
     // Parse the input into a vector of EngineParts
     for line in lines {
-        for capture in line_regex.captures_iter(line) {
-            // Get the first capture group - this is the symbol or number
-            let capture = capture.get(0).unwrap();
-            // Get the x coordinate of the capture group, which is the start of the capture group in the line
-            let x = capture.start() as i32;
-            let contents = capture.as_str().to_string();
-            let width = contents.len() as i32;
-            let is_number = contents.parse::<i32>().is_ok();
-            let engine_part = EnginePart {
-                x,
-                y,
-                width,
-                height: 1,
-                is_number,
-                contents,
-            };
-            engine_parts.push(engine_part);
-        }
+        engine_parts.extend(tokenize_line(line, y, &line_regex));
 
         y += 1;
     }
@@ -119,32 +175,132 @@ fn load_parse_input() -> (Vec<EnginePart>, RTree<EnginePart>) {
 
 // Line could look like this:
 // ....=.........370...........................48..456......424...-.341*.....554...*807.571............971..958............166......*..........
+fn exit_with_usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("Usage: day3 [--symbols <characters>] [--neighbourhood <8|4|radius:N>] [--gear-symbol <symbol>] [--gear-neighbours <N|N-M>] [--gear-combine <product|sum|max>] [--set x,y,c]... [--assemblies] [--render <ansi|html>]");
+    std::process::exit(1);
+}
+
 fn main() {
-    let (engine_parts, rtree) = load_parse_input();
+    // Edits are applied on top of the schematic one by one, everything else configures the schematic
+    let mut config_args = Vec::new();
+    let mut edits = Vec::new();
+    let mut show_assemblies = false;
+    let mut render_format = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--assemblies" {
+            show_assemblies = true;
+        } else if arg == "--render" {
+            let format = args.next().ok_or("Missing value for --render").and_then(|value| renderer::RenderFormat::try_from(value.as_str()));
+            render_format = Some(format.unwrap_or_else(|error| exit_with_usage(error)));
+        } else if arg == "--set" {
+            let edit = args.next().ok_or("Missing value for --set").and_then(|value| editor::parse_edit(&value));
+            edits.push(edit.unwrap_or_else(|error| exit_with_usage(error)));
+        } else {
+            config_args.push(arg);
+        }
+    }
+
+    let config = SchematicConfig::from_args(&config_args).unwrap_or_else(|error| exit_with_usage(error));
+
+    if !edits.is_empty() {
+        let mut schematic = editor::EditableSchematic::new(include_str!("input.txt"), config);
+
+        for (x, y, value) in edits {
+            schematic.set_cell(x, y, value).unwrap_or_else(|error| exit_with_usage(error));
+            println!("After setting ({}, {}) to '{}': part 1 sum is {}, part 2 sum is {}", x, y, value, schematic.part1_total(), schematic.part2_total());
+        }
+
+        return;
+    }
+
+    let (engine_parts, rtree) = load_parse_input(&config.symbols);
     
-    let part1_sum = part1(&engine_parts, &rtree);
-    let part2_sum = part2(&engine_parts, &rtree);
+    let part1_sum = part1(&engine_parts, &rtree, config.neighbourhood);
+    let part2_sum = part2(&engine_parts, &rtree, config.neighbourhood, &config.gear_rule);
 
     println!("The part 1 sum is {}", part1_sum);
     println!("The part 2 sum is {}", part2_sum);
+
+    if let Some(format) = render_format {
+        let input = include_str!("input.txt");
+        let width = input.lines().map(|line| line.chars().count()).max().unwrap_or(0) as i32;
+        let height = input.lines().count() as i32;
+
+        print!("{}", renderer::render(&engine_parts, &rtree, &config, width, height, format));
+    }
+
+    if show_assemblies {
+        let assemblies = assemblies::find_assemblies(&engine_parts, &rtree, &config);
+        print!("{}", assemblies::assembly_table(&assemblies));
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use config::GearCombine;
 
     #[test]
     fn test_part1() {
-        let (engine_parts, rtree) = load_parse_input();
-        let part1_sum = part1(&engine_parts, &rtree);
+        let (engine_parts, rtree) = load_parse_input(&SymbolSet::AnyNonDigitNonDot);
+        let part1_sum = part1(&engine_parts, &rtree, Neighbourhood::EightConnected);
         assert_eq!(part1_sum, 536576);
     }
 
     #[test]
     fn test_part2() {
-        let (engine_parts, rtree) = load_parse_input();
-        let part2_sum = part2(&engine_parts, &rtree);
+        let (engine_parts, rtree) = load_parse_input(&SymbolSet::AnyNonDigitNonDot);
+        let part2_sum = part2(&engine_parts, &rtree, Neighbourhood::EightConnected, &GearRule::default());
         assert_eq!(part2_sum, 75741499);
     }
+
+    #[test]
+    fn test_example_default_config() {
+        let (engine_parts, rtree) = parse_schematic(include_str!("example.txt"), &SymbolSet::AnyNonDigitNonDot);
+
+        assert_eq!(part1(&engine_parts, &rtree, Neighbourhood::EightConnected), 4361);
+        assert_eq!(part2(&engine_parts, &rtree, Neighbourhood::EightConnected, &GearRule::default()), 467835);
+    }
+
+    #[test]
+    fn test_example_four_connected() {
+        let (engine_parts, rtree) = parse_schematic(include_str!("example.txt"), &SymbolSet::AnyNonDigitNonDot);
+
+        // 467, 592 and 755 only touch a symbol diagonally
+        assert_eq!(part1(&engine_parts, &rtree, Neighbourhood::FourConnected), 35 + 633 + 617 + 664 + 598);
+    }
+
+    #[test]
+    fn test_custom_symbols_and_radius() {
+        // Only '#' counts as a symbol, so the '~' is empty space
+        let (engine_parts, rtree) = parse_schematic("12..~.\n......\n..#..5", &SymbolSet::custom("#"));
+        assert_eq!(engine_parts.len(), 3);
+
+        assert_eq!(part1(&engine_parts, &rtree, Neighbourhood::EightConnected), 0);
+        assert_eq!(part1(&engine_parts, &rtree, Neighbourhood::Radius(2)), 12);
+        assert_eq!(part1(&engine_parts, &rtree, Neighbourhood::Radius(3)), 17);
+
+        // A radius reaching past the i32 range still finds every part
+        let radius = Neighbourhood::try_from("radius:2147483647").unwrap();
+        assert_eq!(part1(&engine_parts, &rtree, radius), 17);
+    }
+
+    #[test]
+    fn test_example_gear_rules() {
+        let (engine_parts, rtree) = parse_schematic(include_str!("example.txt"), &SymbolSet::AnyNonDigitNonDot);
+
+        // The lone '*' next to 617 counts once single-neighbour gears are allowed
+        let rule = GearRule { neighbours: 1..=2, combine: GearCombine::Sum, ..GearRule::default() };
+        assert_eq!(part2(&engine_parts, &rtree, Neighbourhood::EightConnected, &rule), 467 + 35 + 617 + 755 + 598);
+
+        let rule = GearRule { combine: GearCombine::Max, ..GearRule::default() };
+        assert_eq!(part2(&engine_parts, &rtree, Neighbourhood::EightConnected, &rule), 467 + 755);
+
+        let rule = GearRule { symbol: "#".to_string(), neighbours: 1..=1, combine: GearCombine::Product };
+        assert_eq!(part2(&engine_parts, &rtree, Neighbourhood::EightConnected, &rule), 633);
+    }
 }
@@ -0,0 +1,188 @@
+use std::convert::TryFrom;
+
+use rstar::RTree;
+
+use super::{gear_ratio, part_number_value, EnginePart};
+use super::config::SchematicConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderFormat {
+    Ansi,
+    Html,
+}
+
+impl TryFrom<&str> for RenderFormat {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "ansi" => Ok(RenderFormat::Ansi),
+            "html" => Ok(RenderFormat::Html),
+            _ => Err("Invalid render format"),
+        }
+    }
+}
+
+// What a part turned out to be when solving the puzzle, which decides how it is highlighted
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PartClass {
+    CountedNumber,
+    IgnoredNumber,
+    Gear,
+    Symbol,
+}
+
+impl PartClass {
+    fn ansi_style(&self) -> &'static str {
+        match self {
+            PartClass::CountedNumber => "\x1b[32m",
+            PartClass::IgnoredNumber => "\x1b[31m",
+            PartClass::Gear => "\x1b[1;33m",
+            PartClass::Symbol => "\x1b[36m",
+        }
+    }
+
+    fn html_class(&self) -> &'static str {
+        match self {
+            PartClass::CountedNumber => "counted",
+            PartClass::IgnoredNumber => "ignored",
+            PartClass::Gear => "gear",
+            PartClass::Symbol => "symbol",
+        }
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_DIM: &str = "\x1b[2m";
+
+const HTML_STYLE: &str = "<style>\n\
+    pre.schematic { background: #0f0f23; color: #555; }\n\
+    pre.schematic .counted { color: #00cc00; }\n\
+    pre.schematic .ignored { color: #ff4444; }\n\
+    pre.schematic .gear { color: #ffff66; font-weight: bold; }\n\
+    pre.schematic .symbol { color: #66ccff; }\n\
+    </style>\n";
+
+fn classify(engine_part: &EnginePart, rtree: &RTree<EnginePart>, config: &SchematicConfig) -> PartClass {
+    if engine_part.is_number {
+        match part_number_value(engine_part, rtree, config.neighbourhood) {
+            Some(_) => PartClass::CountedNumber,
+            None => PartClass::IgnoredNumber,
+        }
+    } else {
+        match gear_ratio(engine_part, rtree, config.neighbourhood, &config.gear_rule) {
+            Some(_) => PartClass::Gear,
+            None => PartClass::Symbol,
+        }
+    }
+}
+
+fn escape_html(contents: &str) -> String {
+    contents.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn render_empty(count: i32, format: RenderFormat) -> String {
+    if count <= 0 {
+        return String::new();
+    }
+
+    let dots = ".".repeat(count as usize);
+
+    match format {
+        RenderFormat::Ansi => format!("{}{}{}", ANSI_DIM, dots, ANSI_RESET),
+        RenderFormat::Html => dots,
+    }
+}
+
+// Renders the schematic from the engine parts alone. Anything that isn't a part is drawn as empty space,
+// and each row is padded with empty space up to the given width.
+pub fn render(engine_parts: &[EnginePart], rtree: &RTree<EnginePart>, config: &SchematicConfig, width: i32, height: i32, format: RenderFormat) -> String {
+    let mut rows: Vec<Vec<&EnginePart>> = vec![Vec::new(); height.max(0) as usize];
+    for engine_part in engine_parts {
+        if engine_part.y >= 0 && engine_part.y < height {
+            rows[engine_part.y as usize].push(engine_part);
+        }
+    }
+
+    let mut output = String::new();
+
+    if format == RenderFormat::Html {
+        output.push_str(HTML_STYLE);
+        output.push_str("<pre class=\"schematic\">\n");
+    }
+
+    for row in rows.iter_mut() {
+        row.sort_by_key(|part| part.x);
+
+        let mut x = 0;
+        for engine_part in row.iter() {
+            output.push_str(&render_empty(engine_part.x - x, format));
+
+            let class = classify(engine_part, rtree, config);
+            match format {
+                RenderFormat::Ansi => {
+                    output.push_str(&format!("{}{}{}", class.ansi_style(), engine_part.contents, ANSI_RESET));
+                }
+                RenderFormat::Html => {
+                    output.push_str(&format!("<span class=\"{}\">{}</span>", class.html_class(), escape_html(&engine_part.contents)));
+                }
+            }
+
+            x = engine_part.x + engine_part.width;
+        }
+
+        output.push_str(&render_empty(width - x, format));
+        output.push('\n');
+    }
+
+    if format == RenderFormat::Html {
+        output.push_str("</pre>\n");
+    }
+
+    output
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_schematic;
+
+    fn strip_ansi(output: &str) -> String {
+        let mut stripped = String::new();
+        let mut in_escape = false;
+
+        for c in output.chars() {
+            match c {
+                '\x1b' => in_escape = true,
+                'm' if in_escape => in_escape = false,
+                _ if in_escape => {}
+                _ => stripped.push(c),
+            }
+        }
+
+        stripped
+    }
+
+    #[test]
+    fn test_render_ansi_reproduces_schematic() {
+        let config = SchematicConfig::default();
+        let (engine_parts, rtree) = parse_schematic(include_str!("example.txt"), &config.symbols);
+        let output = render(&engine_parts, &rtree, &config, 10, 10, RenderFormat::Ansi);
+
+        assert_eq!(strip_ansi(&output), include_str!("example.txt"));
+        assert!(output.contains("\x1b[31m114\x1b[0m"));
+        assert!(output.contains("\x1b[32m467\x1b[0m"));
+        assert!(output.contains("\x1b[1;33m*\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_html() {
+        let config = SchematicConfig::default();
+        let (engine_parts, rtree) = parse_schematic("12&<\n*3..", &config.symbols);
+        let output = render(&engine_parts, &rtree, &config, 4, 2, RenderFormat::Html);
+
+        assert!(output.contains("<pre class=\"schematic\">\n<span class=\"counted\">12</span><span class=\"symbol\">&amp;</span><span class=\"symbol\">&lt;</span>\n"));
+        assert!(output.contains("<span class=\"gear\">*</span><span class=\"counted\">3</span>..\n</pre>"));
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::{Duration, Instant};
+
+use super::number_set::NumberSet;
+
+// Small xorshift generator so decks can be generated deterministically without pulling in a crate
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn next_number(&mut self, max: i32) -> i32 {
+        (self.next() % max as u64) as i32
+    }
+}
+
+struct GeneratedCard {
+    winning_numbers: Vec<i32>,
+    draw_numbers: Vec<i32>,
+}
+
+// Cards with the given amount of numbers on each side. Numbers go up to 4x that amount, so plenty
+// of them land outside the bitset once cards get large.
+fn generate_deck(cards: usize, numbers_per_card: usize, seed: u64) -> Vec<GeneratedCard> {
+    let mut rng = XorShift(seed.max(1));
+    let max_number = (numbers_per_card as i32 * 4).max(1);
+
+    (0..cards)
+        .map(|_| GeneratedCard {
+            winning_numbers: (0..numbers_per_card).map(|_| rng.next_number(max_number)).collect(),
+            draw_numbers: (0..numbers_per_card).map(|_| rng.next_number(max_number)).collect(),
+        })
+        .collect()
+}
+
+// The original way of counting matches: look up every winning number in the drawn numbers
+fn count_matches_naive(winning_numbers: &[i32], draw_numbers: &[i32]) -> usize {
+    winning_numbers.iter().filter(|number| draw_numbers.contains(number)).count()
+}
+
+fn time<T>(run: impl Fn() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = run();
+    (result, start.elapsed())
+}
+
+// Compares the naive Vec lookup, per-number set lookups and the bitset popcount on a generated deck.
+// Winning numbers are deduplicated first, like a card that repeats one would be rejected.
+pub fn run(cards: usize, numbers_per_card: usize) {
+    let deck = generate_deck(cards, numbers_per_card, 2023)
+        .into_iter()
+        .map(|card| {
+            let mut winning_numbers = card.winning_numbers;
+            winning_numbers.sort();
+            winning_numbers.dedup();
+            GeneratedCard { winning_numbers, draw_numbers: card.draw_numbers }
+        })
+        .collect::<Vec<_>>();
+
+    let sets = deck.iter()
+        .map(|card| (
+            card.winning_numbers.iter().copied().collect::<NumberSet>(),
+            card.draw_numbers.iter().copied().collect::<NumberSet>(),
+        ))
+        .collect::<Vec<_>>();
+
+    let (naive, naive_time) = time(|| {
+        deck.iter().map(|card| count_matches_naive(&card.winning_numbers, &card.draw_numbers)).sum::<usize>()
+    });
+
+    let (lookup, lookup_time) = time(|| {
+        deck.iter()
+            .zip(sets.iter())
+            .map(|(card, (_, draw_numbers))| card.winning_numbers.iter().filter(|number| draw_numbers.contains(**number)).count())
+            .sum::<usize>()
+    });
+
+    let (popcount, popcount_time) = time(|| {
+        sets.iter().map(|(winning_numbers, draw_numbers)| winning_numbers.intersection_count(draw_numbers)).sum::<usize>()
+    });
+
+    assert_eq!(naive, lookup);
+    assert_eq!(naive, popcount);
+
+    println!("{} cards with {} numbers per side, {} matches in total", cards, numbers_per_card, naive);
+    println!("Vec::contains:     {:?}", naive_time);
+    println!("NumberSet lookups: {:?}", lookup_time);
+    println!("Bitset popcount:   {:?}", popcount_time);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_matches_agree_with_naive_count() {
+        for numbers_per_card in [5, 32, 300] {
+            for card in generate_deck(50, numbers_per_card, 7) {
+                let mut winning_numbers = card.winning_numbers.clone();
+                winning_numbers.sort();
+                winning_numbers.dedup();
+
+                let winning_set = winning_numbers.iter().copied().collect::<NumberSet>();
+                let draw_set = card.draw_numbers.iter().copied().collect::<NumberSet>();
+
+                assert_eq!(winning_set.intersection_count(&draw_set), count_matches_naive(&winning_numbers, &card.draw_numbers));
+            }
+        }
+    }
+}
@@ -1,38 +1,27 @@
-use std::collections::HashMap;
 use std::convert::TryFrom;
 
+mod bench;
+mod number_set;
+mod scoring;
+mod streaming;
+
+use number_set::NumberSet;
+use scoring::{CopyCascade, Legacy, ScoringPolicy};
+
 struct Card {
     number: i32,
-    winning_numbers: Vec<i32>,
-    draw_numbers: Vec<i32>,
+    winning_numbers: NumberSet,
+    draw_numbers: NumberSet,
 }
 
 impl Card {
     fn get_matches(&self) -> usize {
-        let winning_numbers = &self.winning_numbers;
-        let draw_numbers = &self.draw_numbers;
-
-        let mut matches: usize = 0;
-
-        for winning_number in winning_numbers {
-            if draw_numbers.contains(winning_number) {
-                matches += 1;
-            }
-        }
-
-        matches
+        self.winning_numbers.intersection_count(&self.draw_numbers)
     }
 
-    fn get_sum(&self) -> i32 {
-        let matches = self.get_matches();
-
-        let mut card_sum = 1;
-
-        for _ in 1..matches {
-            card_sum *= 2;
-        }
-
-        card_sum
+    // None if the points don't fit in a u64
+    fn get_sum(&self) -> Option<u64> {
+        Legacy::points(self.get_matches()).and_then(|points| u64::try_from(points).ok())
     }
 }
 
@@ -56,16 +45,19 @@ impl TryFrom<&str> for Card {
             return Err("Invalid winning/draw numbers format");
         }
 
-        // Parse the winning numbers
-        let winning_numbers: Result<Vec<i32>, _> = number_parts[0]
-            .split(" ")
-            .filter(|s| !s.is_empty())
-            .map(|s| s.parse::<i32>())
-            .collect();
-        let winning_numbers = winning_numbers.map_err(|_| "Invalid winning numbers")?;
+        // Parse the winning numbers. The numbers are kept as a set, so a repeated winning number could
+        // only match once, where it used to match once for every time it's listed. Rather than
+        // quietly scoring such a card differently, it's rejected.
+        let mut winning_numbers = NumberSet::default();
+        for number in number_parts[0].split(" ").filter(|s| !s.is_empty()) {
+            let number = number.parse::<i32>().map_err(|_| "Invalid winning numbers")?;
+            if !winning_numbers.insert(number) {
+                return Err("Card repeats a winning number");
+            }
+        }
 
         // Parse the draw numbers
-        let draw_numbers: Result<Vec<i32>, _> = number_parts[1]
+        let draw_numbers: Result<NumberSet, _> = number_parts[1]
             .split(" ")
             .filter(|s| !s.is_empty())
             .map(|s| s.parse::<i32>())
@@ -81,7 +73,10 @@ impl TryFrom<&str> for Card {
 }
 
 fn load_parse_input() -> Vec<Card> {
-    let input = include_str!("input.txt");
+    parse_cards(include_str!("input.txt"))
+}
+
+fn parse_cards(input: &str) -> Vec<Card> {
     let lines = input.lines().collect::<Vec<_>>();
 
     let mut cards = Vec::new();
@@ -94,46 +89,64 @@ fn load_parse_input() -> Vec<Card> {
     cards
 }
 
-fn part1(cards: &Vec<Card>) -> i32 {
-    let mut part1_sum = 0;
+fn part1(cards: &Vec<Card>) -> u64 {
+    cards.iter()
+        .try_fold(0u64, |total, card| total.checked_add(card.get_sum()?))
+        .expect("Part 1 total overflowed")
+}
 
-    for card in cards.iter() {
+fn part2(cards: &Vec<Card>) -> u64 {
+    CopyCascade::default().score(cards).expect("Part 2 total overflowed")
+}
 
-        let card_sum = card.get_sum();
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("Usage: day4 [--policy <doubling|legacy|linear|fibonacci|cascade[:multiplier[:max fan-out]]>]... [--stream <file>] [--bench <numbers per card>]");
+    std::process::exit(1);
+}
 
-        part1_sum += card_sum;
+// Scores a file of cards without loading the whole file, reading from stdin if the path is "-"
+fn run_stream(path: &str) {
+    let totals = if path == "-" {
+        streaming::stream_cards(std::io::stdin().lock(), &CopyCascade::default())
+    } else {
+        let file = std::fs::File::open(path).unwrap_or_else(|_| exit_with_error("Failed to open card file"));
+        streaming::stream_cards(std::io::BufReader::new(file), &CopyCascade::default())
+    };
+
+    match totals {
+        Ok(totals) => {
+            println!("Cards: {}", totals.cards);
+            println!("Part 1: {}", totals.points);
+            println!("Part 2: {}", totals.copies);
+        }
+        Err(error) => exit_with_error(error),
     }
-
-    part1_sum
 }
 
-fn part2(cards: &Vec<Card>) -> i32 {
-    let mut card_copies_map: HashMap<usize, i32> = HashMap::new();
-
-    for (pos, card) in cards.iter().enumerate() {
-        let matches = card.get_matches();
-
-        let copies = card_copies_map.get(&pos).cloned().unwrap_or(1);
-
-        for i in (pos + 1)..(pos + 1 + matches) {
-            let index = i as usize;
-            let existing_copies = card_copies_map.get(&index).cloned().unwrap_or(1);
-            let new_copies = existing_copies + copies;
-            card_copies_map.insert(index, new_copies);
+fn main() {
+    // "--policy <name>" scores the cards with that policy, and "--stream <file>" scores another file of cards
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        for pair in args.chunks(2) {
+            match pair {
+                [flag, name] if flag == "--policy" => {
+                    let policy = scoring::policy_from_name(name).unwrap_or_else(|error| exit_with_error(error));
+                    let score = policy.score(&load_parse_input()).unwrap_or_else(|error| exit_with_error(error));
+                    println!("{}: {}", policy.name(), score);
+                }
+                [flag, path] if flag == "--stream" => run_stream(path),
+                [flag, numbers] if flag == "--bench" => {
+                    let numbers_per_card = numbers.parse::<usize>().unwrap_or_else(|_| exit_with_error("Invalid number of numbers per card"));
+                    bench::run(1000, numbers_per_card);
+                }
+                _ => exit_with_error("Invalid arguments"),
+            }
         }
-    }
 
-    let mut part2_sum = 0;
-    // Count all copies of cards
-    for (pos, _) in cards.iter().enumerate() {
-        let copies = card_copies_map.get(&pos).cloned().unwrap_or(1);
-        part2_sum += copies
+        return;
     }
 
-    part2_sum
-}
-
-fn main() {
     let cards = load_parse_input();
 
     let part1_sum = part1(&cards);
@@ -152,8 +165,8 @@ mod tests {
         let line = "Card  17: 66 49 60 87  9 35 86 80 40 26 | 48  1 82 34 53 78 30  4 86 22 97 26 54  2 49 88 23 94 13 90 32 98 38 51 25";
         let card = Card::try_from(line).unwrap();
         assert_eq!(card.number, 17);
-        assert_eq!(card.winning_numbers, vec![66, 49, 60, 87, 9, 35, 86, 80, 40, 26]);
-        assert_eq!(card.draw_numbers, vec![48, 1, 82, 34, 53, 78, 30, 4, 86, 22, 97, 26, 54, 2, 49, 88, 23, 94, 13, 90, 32, 98, 38, 51, 25]);
+        assert_eq!(card.winning_numbers, vec![66, 49, 60, 87, 9, 35, 86, 80, 40, 26].into_iter().collect::<NumberSet>());
+        assert_eq!(card.draw_numbers, vec![48, 1, 82, 34, 53, 78, 30, 4, 86, 22, 97, 26, 54, 2, 49, 88, 23, 94, 13, 90, 32, 98, 38, 51, 25].into_iter().collect::<NumberSet>());
     }
 
     #[test]
@@ -164,12 +177,44 @@ mod tests {
         assert_eq!(matches, 3);
     }
 
+    #[test]
+    fn test_get_card_matches_outside_bitset() {
+        let line = "Card 1: 5 200 -7 1000 | 1000 5 -7 6 201";
+        let card = Card::try_from(line).unwrap();
+        assert_eq!(card.get_matches(), 3);
+    }
+
+    #[test]
+    fn test_repeated_numbers() {
+        assert_eq!(Card::try_from("Card 1: 5 7 5 | 5 6 7").err(), Some("Card repeats a winning number"));
+        assert_eq!(Card::try_from("Card 1: 500 7 500 | 500").err(), Some("Card repeats a winning number"));
+
+        // Repeated draw numbers never counted more than once
+        let card = Card::try_from("Card 1: 5 7 | 5 5 7 7 7").unwrap();
+        assert_eq!(card.get_matches(), 2);
+    }
+
     #[test]
     fn test_get_card_sum() {
         let line = "Card  17: 66 49 60 87  9 35 86 80 40 26 | 48  1 82 34 53 78 30  4 86 22 97 26 54  2 49 88 23 94 13 90 32 98 38 51 25";
         let card = Card::try_from(line).unwrap();
         let card_sum = card.get_sum();
-        assert_eq!(card_sum, 4);
+        assert_eq!(card_sum, Some(4));
+    }
+
+    #[test]
+    fn test_get_card_sum_without_matches() {
+        let line = "Card   5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36";
+        let card = Card::try_from(line).unwrap();
+        assert_eq!(card.get_sum(), Some(1));
+    }
+
+    #[test]
+    fn test_part1_example() {
+        let cards = parse_cards(include_str!("example.txt"));
+
+        // Cards 5 and 6 have no matches but still score 1 point each
+        assert_eq!(part1(&cards), 15);
     }
 
     #[test]
@@ -0,0 +1,115 @@
+use std::iter::FromIterator;
+
+// A set of card numbers. Numbers in 0..128 live in a single u128 bitset, which covers every
+// number on a normal scratchcard, and anything outside that range falls back to a sorted Vec.
+// Like any set, it holds a number once no matter how often it's inserted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumberSet {
+    bits: u128,
+    overflow: Vec<i32>,
+}
+
+impl NumberSet {
+    fn bit(number: i32) -> Option<u128> {
+        if (0..128).contains(&number) {
+            Some(1 << number)
+        } else {
+            None
+        }
+    }
+
+    // Whether the number wasn't in the set yet
+    pub fn insert(&mut self, number: i32) -> bool {
+        match NumberSet::bit(number) {
+            Some(bit) => {
+                let new = self.bits & bit == 0;
+                self.bits |= bit;
+                new
+            }
+            None => match self.overflow.binary_search(&number) {
+                Ok(_) => false,
+                Err(index) => {
+                    self.overflow.insert(index, number);
+                    true
+                }
+            },
+        }
+    }
+
+    pub fn contains(&self, number: i32) -> bool {
+        match NumberSet::bit(number) {
+            Some(bit) => self.bits & bit != 0,
+            None => self.overflow.binary_search(&number).is_ok(),
+        }
+    }
+
+    // Number of values in both sets: a popcount for the bitsets, and a merge of the sorted overflow values
+    pub fn intersection_count(&self, other: &NumberSet) -> usize {
+        let mut count = (self.bits & other.bits).count_ones() as usize;
+
+        let (mut i, mut j) = (0, 0);
+        while i < self.overflow.len() && j < other.overflow.len() {
+            match self.overflow[i].cmp(&other.overflow[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    count += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+
+        count
+    }
+}
+
+impl FromIterator<i32> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = i32>>(iter: I) -> Self {
+        let mut set = NumberSet::default();
+        for number in iter {
+            set.insert(number);
+        }
+        set
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_contains() {
+        let set = vec![0, 5, 127, 128, -3, 1000, 5].into_iter().collect::<NumberSet>();
+
+        assert_eq!(set.overflow, vec![-3, 128, 1000]);
+        for number in [0, 5, 127, 128, -3, 1000] {
+            assert!(set.contains(number));
+        }
+        assert!(!set.contains(6));
+        assert!(!set.contains(129));
+        assert!(!NumberSet::default().contains(0));
+    }
+
+    #[test]
+    fn test_insert_twice() {
+        let mut set = NumberSet::default();
+
+        assert!(set.insert(5));
+        assert!(!set.insert(5));
+        assert!(set.insert(1000));
+        assert!(!set.insert(1000));
+        assert_eq!(set, vec![5, 1000].into_iter().collect::<NumberSet>());
+    }
+
+    #[test]
+    fn test_intersection_count() {
+        let a = vec![1, 2, 3, 200, 300, -1].into_iter().collect::<NumberSet>();
+        let b = vec![2, 3, 4, 300, 400, -1].into_iter().collect::<NumberSet>();
+
+        assert_eq!(a.intersection_count(&b), 4);
+        assert_eq!(b.intersection_count(&a), 4);
+        assert_eq!(a.intersection_count(&NumberSet::default()), 0);
+    }
+}
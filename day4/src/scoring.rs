@@ -0,0 +1,286 @@
+use super::Card;
+
+// A way of turning a deck of scratchcards into a total score
+pub trait ScoringPolicy {
+    fn name(&self) -> String;
+
+    fn score(&self, cards: &[Card]) -> Result<u64, &'static str>;
+}
+
+// Policies where every card is worth some points based only on its own matches
+fn sum_card_points(cards: &[Card], points: impl Fn(usize) -> Option<u128>) -> Result<u64, &'static str> {
+    cards.iter().try_fold(0u64, |total, card| {
+        let points = points(card.get_matches())
+            .and_then(|points| u64::try_from(points).ok())
+            .ok_or("Card points overflowed")?;
+        total.checked_add(points).ok_or("Total points overflowed")
+    })
+}
+
+// 1 point for the first match, doubled for every match after that. No matches are worth nothing.
+pub struct Doubling;
+
+impl Doubling {
+    // None once the points no longer fit in a u128, from 129 matches on
+    pub fn points(matches: usize) -> Option<u128> {
+        if matches == 0 {
+            Some(0)
+        } else {
+            u32::try_from(matches - 1).ok().and_then(|shift| 1u128.checked_shl(shift))
+        }
+    }
+}
+
+impl ScoringPolicy for Doubling {
+    fn name(&self) -> String {
+        "doubling".to_string()
+    }
+
+    fn score(&self, cards: &[Card]) -> Result<u64, &'static str> {
+        sum_card_points(cards, Doubling::points)
+    }
+}
+
+// Doubling the way part 1 has always been scored, where a card without matches is still worth 1 point
+pub struct Legacy;
+
+impl Legacy {
+    // None once the points no longer fit in a u128, from 129 matches on
+    pub fn points(matches: usize) -> Option<u128> {
+        if matches == 0 {
+            Some(1)
+        } else {
+            Doubling::points(matches)
+        }
+    }
+}
+
+impl ScoringPolicy for Legacy {
+    fn name(&self) -> String {
+        "legacy".to_string()
+    }
+
+    fn score(&self, cards: &[Card]) -> Result<u64, &'static str> {
+        sum_card_points(cards, Legacy::points)
+    }
+}
+
+// 1 point per match
+pub struct Linear;
+
+impl ScoringPolicy for Linear {
+    fn name(&self) -> String {
+        "linear".to_string()
+    }
+
+    fn score(&self, cards: &[Card]) -> Result<u64, &'static str> {
+        sum_card_points(cards, |matches| Some(matches as u128))
+    }
+}
+
+// Points follow the Fibonacci sequence: 0, 1, 2, 3, 5, 8, ... for 0, 1, 2, 3, 4, 5, ... matches
+pub struct Fibonacci;
+
+impl Fibonacci {
+    // None once the points no longer fit in a u64
+    pub fn points(matches: usize) -> Option<u64> {
+        if matches == 0 {
+            return Some(0);
+        }
+
+        let (mut previous, mut current) = (1u64, 1u64);
+        for _ in 1..matches {
+            let next = previous.checked_add(current)?;
+            previous = current;
+            current = next;
+        }
+
+        Some(current)
+    }
+}
+
+impl ScoringPolicy for Fibonacci {
+    fn name(&self) -> String {
+        "fibonacci".to_string()
+    }
+
+    fn score(&self, cards: &[Card]) -> Result<u64, &'static str> {
+        sum_card_points(cards, |matches| Fibonacci::points(matches).map(u128::from))
+    }
+}
+
+// Every match wins a copy of one of the following cards, and copies win copies of their own.
+// The score is the total number of cards you end up with. The fan-out decides how many following
+// cards a card with a given number of matches wins copies of, which is the number of matches by default.
+pub struct CopyCascade {
+    pub fan_out_multiplier: usize,
+    pub max_fan_out: Option<usize>,
+}
+
+impl Default for CopyCascade {
+    fn default() -> Self {
+        CopyCascade {
+            fan_out_multiplier: 1,
+            max_fan_out: None,
+        }
+    }
+}
+
+impl CopyCascade {
+    // Saturates instead of overflowing, a fan-out that large reaches past the end of any table anyway
+    pub fn fan_out(&self, matches: usize) -> usize {
+        let fan_out = matches.saturating_mul(self.fan_out_multiplier);
+
+        match self.max_fan_out {
+            Some(max_fan_out) => fan_out.min(max_fan_out),
+            None => fan_out,
+        }
+    }
+}
+
+impl ScoringPolicy for CopyCascade {
+    fn name(&self) -> String {
+        match self.max_fan_out {
+            Some(max_fan_out) => format!("cascade:{}:{}", self.fan_out_multiplier, max_fan_out),
+            None => format!("cascade:{}", self.fan_out_multiplier),
+        }
+    }
+
+    fn score(&self, cards: &[Card]) -> Result<u64, &'static str> {
+        // Every card starts out with just the original
+        let mut copies = vec![1u64; cards.len()];
+
+        for (pos, card) in cards.iter().enumerate() {
+            let fan_out = self.fan_out(card.get_matches());
+
+            // Copies can't be won of cards past the end of the table
+            for i in (pos + 1)..usize::min((pos + 1).saturating_add(fan_out), cards.len()) {
+                copies[i] = copies[i].checked_add(copies[pos]).ok_or("Card copies overflowed")?;
+            }
+        }
+
+        copies.iter().try_fold(0u64, |total, copies| total.checked_add(*copies)).ok_or("Total copies overflowed")
+    }
+}
+
+// Picks a policy by name: "doubling", "legacy", "linear", "fibonacci" or "cascade[:multiplier[:max fan-out]]"
+pub fn policy_from_name(name: &str) -> Result<Box<dyn ScoringPolicy>, &'static str> {
+    let mut parts = name.split(':');
+
+    match parts.next() {
+        Some("doubling") => Ok(Box::new(Doubling)),
+        Some("legacy") => Ok(Box::new(Legacy)),
+        Some("linear") => Ok(Box::new(Linear)),
+        Some("fibonacci") => Ok(Box::new(Fibonacci)),
+        Some("cascade") => {
+            let mut cascade = CopyCascade::default();
+
+            if let Some(multiplier) = parts.next() {
+                cascade.fan_out_multiplier = multiplier.parse().map_err(|_| "Invalid cascade fan-out multiplier")?;
+            }
+            if let Some(max_fan_out) = parts.next() {
+                cascade.max_fan_out = Some(max_fan_out.parse().map_err(|_| "Invalid cascade maximum fan-out")?);
+            }
+            if parts.next().is_some() {
+                return Err("Too many cascade options");
+            }
+
+            Ok(Box::new(cascade))
+        }
+        _ => Err("Unknown scoring policy"),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn example_cards() -> Vec<Card> {
+        include_str!("example.txt").lines().map(|line| Card::try_from(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_existing_behaviours_on_example() {
+        let cards = example_cards();
+
+        assert_eq!(Doubling.score(&cards), Ok(13));
+        // Cards 5 and 6 still score 1 point each
+        assert_eq!(Legacy.score(&cards), Ok(15));
+        assert_eq!(CopyCascade::default().score(&cards), Ok(30));
+    }
+
+    #[test]
+    fn test_zero_matches() {
+        // Card 5 and 6 of the example have no matches
+        let cards = example_cards().into_iter().skip(4).collect::<Vec<_>>();
+
+        assert_eq!(Doubling.score(&cards), Ok(0));
+        assert_eq!(Legacy.score(&cards), Ok(2));
+        assert_eq!(Linear.score(&cards), Ok(0));
+        assert_eq!(Fibonacci.score(&cards), Ok(0));
+        // With no matches, you just keep the originals
+        assert_eq!(CopyCascade::default().score(&cards), Ok(2));
+    }
+
+    #[test]
+    fn test_card_points() {
+        assert_eq!((0..6).map(|matches| Doubling::points(matches).unwrap()).collect::<Vec<_>>(), vec![0, 1, 2, 4, 8, 16]);
+        assert_eq!((0..6).map(|matches| Legacy::points(matches).unwrap()).collect::<Vec<_>>(), vec![1, 1, 2, 4, 8, 16]);
+        assert_eq!((0..6).map(|matches| Fibonacci::points(matches).unwrap()).collect::<Vec<_>>(), vec![0, 1, 2, 3, 5, 8]);
+        assert_eq!(Linear.score(&example_cards()), Ok(4 + 2 + 2 + 1));
+    }
+
+    #[test]
+    fn test_custom_fan_out() {
+        let cards = example_cards();
+
+        // Capping the fan-out at 1 means every card with matches only copies the next card
+        let capped = CopyCascade { fan_out_multiplier: 1, max_fan_out: Some(1) };
+        assert_eq!(capped.score(&cards), Ok(1 + 2 + 3 + 4 + 5 + 1));
+
+        // Fan-out past the end of the table is ignored
+        let doubled = CopyCascade { fan_out_multiplier: 2, max_fan_out: None };
+        assert_eq!(doubled.fan_out(4), 8);
+        assert!(doubled.score(&cards).unwrap() > CopyCascade::default().score(&cards).unwrap());
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(Doubling::points(64), Some(1 << 63));
+        assert_eq!(Doubling::points(65), Some(1 << 64));
+        assert_eq!(Doubling::points(128), Some(1 << 127));
+        assert_eq!(Doubling::points(129), None);
+        assert_eq!(Doubling::points(usize::MAX), None);
+        assert_eq!(Fibonacci::points(92), Some(12200160415121876738));
+        assert_eq!(Fibonacci::points(93), None);
+
+        // 70 matching numbers on a single card
+        let numbers = (1..=70).map(|number| number.to_string()).collect::<Vec<_>>().join(" ");
+        let cards = vec![Card::try_from(format!("Card 1: {} | {}", numbers, numbers).as_str()).unwrap()];
+        assert_eq!(Doubling.score(&cards), Err("Card points overflowed"));
+        assert_eq!(Linear.score(&cards), Ok(70));
+
+        // The multiplier comes straight from the command line, so any usize has to work
+        let huge = CopyCascade { fan_out_multiplier: usize::MAX, max_fan_out: None };
+        assert_eq!(huge.fan_out(3), usize::MAX);
+        assert_eq!(huge.score(&example_cards()), Ok(1 + 2 + 4 + 8 + 16 + 16));
+
+        // Every card copies all the cards after it, so the copies double with every card
+        let deck = (1..=70).map(|number| Card::try_from(format!("Card {}: 1 | 1", number).as_str()).unwrap()).collect::<Vec<_>>();
+        let everything = CopyCascade { fan_out_multiplier: 100, max_fan_out: None };
+        assert_eq!(everything.score(&deck[..10]), Ok(1023));
+        assert_eq!(everything.score(&deck), Err("Card copies overflowed"));
+    }
+
+    #[test]
+    fn test_policy_from_name() {
+        assert_eq!(policy_from_name("doubling").unwrap().name(), "doubling");
+        assert_eq!(policy_from_name("legacy").unwrap().name(), "legacy");
+        assert_eq!(policy_from_name("cascade").unwrap().name(), "cascade:1");
+        assert_eq!(policy_from_name("cascade:2:3").unwrap().name(), "cascade:2:3");
+        assert!(policy_from_name("cascade:x").is_err());
+        assert!(policy_from_name("bogus").is_err());
+    }
+}
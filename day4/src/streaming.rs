@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io::BufRead;
+
+use super::Card;
+use super::scoring::{CopyCascade, Doubling};
+
+#[derive(Debug, PartialEq)]
+pub struct StreamTotals {
+    pub cards: u64,
+    pub points: u128,
+    pub copies: u128,
+}
+
+// Scores the cards one line at a time, without ever holding more than one card in memory.
+// The copies a card wins go to a run of the following cards, so rather than a slot for every card
+// in that run, the state between cards is the number of copies won for the next card plus how many
+// of those stop at which card. Runs stopping at the same card share an entry, so there are never
+// more entries than the largest fan-out seen so far, and a fan-out reaching past any possible end
+// of the file costs a single one.
+pub fn stream_cards<R: BufRead>(reader: R, cascade: &CopyCascade) -> Result<StreamTotals, &'static str> {
+    let mut won_copies: u128 = 0;
+    let mut stopping_copies: BTreeMap<u64, u128> = BTreeMap::new();
+    let mut totals = StreamTotals {
+        cards: 0,
+        points: 0,
+        copies: 0,
+    };
+
+    for line in reader.lines() {
+        let line = line.map_err(|_| "Failed to read card")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let card = Card::try_from(line.as_str())?;
+        let matches = card.get_matches();
+        let position = totals.cards;
+
+        if let Some(stopped) = stopping_copies.remove(&position) {
+            won_copies -= stopped;
+        }
+
+        // The original plus whatever copies earlier cards won of this one
+        let copies = won_copies.checked_add(1).ok_or("Copy count overflowed")?;
+
+        let fan_out = u64::try_from(cascade.fan_out(matches)).unwrap_or(u64::MAX);
+        if fan_out > 0 {
+            won_copies = won_copies.checked_add(copies).ok_or("Copy count overflowed")?;
+
+            // The copies go to the following cards up to, but not including, this one
+            let stop = position.saturating_add(1).saturating_add(fan_out);
+            *stopping_copies.entry(stop).or_insert(0) += copies;
+        }
+
+        let points = Doubling::points(matches).ok_or("Card points overflowed")?;
+
+        totals.cards += 1;
+        totals.points = totals.points.checked_add(points).ok_or("Total points overflowed")?;
+        totals.copies = totals.copies.checked_add(copies).ok_or("Total copies overflowed")?;
+    }
+
+    Ok(totals)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::scoring::{Doubling, ScoringPolicy};
+
+    #[test]
+    fn test_stream_example() {
+        let totals = stream_cards(include_str!("example.txt").as_bytes(), &CopyCascade::default()).unwrap();
+
+        assert_eq!(totals, StreamTotals { cards: 6, points: 13, copies: 30 });
+    }
+
+    #[test]
+    fn test_stream_matches_in_memory_cascade() {
+        let cards = include_str!("example.txt").lines().map(|line| Card::try_from(line).unwrap()).collect::<Vec<_>>();
+
+        let cascades = [
+            CopyCascade { fan_out_multiplier: 2, max_fan_out: None },
+            CopyCascade { fan_out_multiplier: 1, max_fan_out: Some(1) },
+            CopyCascade { fan_out_multiplier: usize::MAX, max_fan_out: None },
+        ];
+
+        for cascade in cascades {
+            let totals = stream_cards(include_str!("example.txt").as_bytes(), &cascade).unwrap();
+
+            assert_eq!(totals.copies, cascade.score(&cards).unwrap() as u128);
+            assert_eq!(totals.points, Doubling.score(&cards).unwrap() as u128);
+        }
+    }
+
+    #[test]
+    fn test_stream_large_deck() {
+        // Every card wins one copy of each of the next 4 cards, so the number of copies grows like a
+        // tetranacci sequence and quickly outgrows an i32
+        let deck = (1..=60)
+            .map(|number| format!("Card {}: 1 2 3 4 | 1 2 3 4", number))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let totals = stream_cards(deck.as_bytes(), &CopyCascade::default()).unwrap();
+
+        assert_eq!(totals.cards, 60);
+        assert_eq!(totals.points, 60 * 8);
+        assert!(totals.copies > i32::MAX as u128);
+    }
+
+    #[test]
+    fn test_stream_huge_fan_out() {
+        // A fan-out that can never be reached still only wins copies of the cards that are there
+        let cascade = CopyCascade { fan_out_multiplier: usize::MAX, max_fan_out: None };
+        let totals = stream_cards(include_str!("example.txt").as_bytes(), &cascade).unwrap();
+
+        assert_eq!(totals.copies, 1 + 2 + 4 + 8 + 16 + 16);
+    }
+
+    #[test]
+    fn test_stream_invalid_card() {
+        assert!(stream_cards("Card 1: 1 2 | 3\nnot a card".as_bytes(), &CopyCascade::default()).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rayon::prelude::*;
+
+use super::{run_translation_pipeline, Mapping};
+use super::interval_map::IntervalMap;
+
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub seed: u128,
+    pub expected: u128,
+    pub actual: u128,
+}
+
+// Cuts the seed ranges into chunks of at most chunk_size seeds, so the work can be spread over every core
+fn chunk_ranges(seed_ranges: &[Range<u128>], chunk_size: u128) -> Vec<Range<u128>> {
+    let chunk_size = chunk_size.max(1);
+    let mut chunks = Vec::new();
+
+    for range in seed_ranges {
+        let mut start = range.start;
+        while start < range.end {
+            let end = range.end.min(start + chunk_size);
+            chunks.push(start..end);
+            start = end;
+        }
+    }
+
+    chunks
+}
+
+// Runs every single seed through the pipeline and returns the lowest location. on_progress is called
+// from the worker threads after each chunk with the number of seeds done so far and the total.
+pub fn scan_seed_ranges<F>(seed_ranges: &[Range<u128>], pipeline: &Vec<Mapping>, chunk_size: u128, on_progress: F) -> Option<u128>
+where
+    F: Fn(u64, u64) + Sync,
+{
+    let chunks = chunk_ranges(seed_ranges, chunk_size);
+    let total = chunks.iter().map(|chunk| (chunk.end - chunk.start) as u64).sum::<u64>();
+    let done = AtomicU64::new(0);
+
+    chunks.par_iter()
+        .filter_map(|chunk| {
+            let minimum = chunk.clone().map(|seed| run_translation_pipeline(seed, pipeline)).min();
+
+            let length = (chunk.end - chunk.start) as u64;
+            on_progress(done.fetch_add(length, Ordering::Relaxed) + length, total);
+
+            minimum
+        })
+        .min()
+}
+
+// Evenly spaced seeds from each range, always including the first and last seed. The seeds are
+// produced one at a time, as asking for more samples than a range has seeds walks the whole range.
+pub fn sample_seeds(seed_ranges: &[Range<u128>], samples_per_range: u64) -> impl Iterator<Item = u128> + '_ {
+    seed_ranges.iter()
+        .filter(|range| range.start < range.end)
+        .flat_map(move |range| {
+            let length = range.end - range.start;
+            let samples = (samples_per_range as u128).min(length);
+
+            // Sample i sits at i * (length - 1) / gaps, which can overflow for large ranges. Splitting the
+            // distance between samples into its whole part and a remainder keeps both products in range.
+            let gaps = samples.saturating_sub(1).max(1);
+            let (step, remainder) = ((length - 1) / gaps, (length - 1) % gaps);
+
+            (0..samples).map(move |i| range.start + i * step + i * remainder / gaps)
+        })
+}
+
+// Checks the composed interval map against running the seeds one table at a time. A sampled seed
+// is a mismatch when the two disagree, or when its location isn't in the interval result.
+pub fn verify_samples(seed_ranges: &[Range<u128>], pipeline: &Vec<Mapping>, samples_per_range: u64) -> Vec<Mismatch> {
+    let composed = IntervalMap::from_pipeline(pipeline);
+    let location_ranges = composed.apply_to_intervals(seed_ranges);
+
+    let mut mismatches = sample_seeds(seed_ranges, samples_per_range)
+        .par_bridge()
+        .filter_map(|seed| {
+            let expected = run_translation_pipeline(seed, pipeline);
+            let actual = composed.apply(seed);
+
+            if expected != actual || !location_ranges.iter().any(|range| range.contains(&expected)) {
+                Some(Mismatch { seed, expected, actual })
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    mismatches.sort_by_key(|mismatch| mismatch.seed);
+    mismatches
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_section, seed_ranges, split_sections};
+
+    fn example() -> (Vec<Range<u128>>, Vec<Mapping>) {
+        let sections = split_sections(include_str!("./example.txt"));
+        let tables = sections[1..].iter().map(|s| parse_section(s)).collect();
+
+        (seed_ranges(sections[0]), tables)
+    }
+
+    #[test]
+    fn test_chunk_ranges() {
+        assert_eq!(chunk_ranges(&[0..10, 20..23], 4), vec![0..4, 4..8, 8..10, 20..23]);
+        let range = 5..7;
+        assert_eq!(chunk_ranges(&[range], 0), vec![5..6, 6..7]);
+    }
+
+    #[test]
+    fn test_scan_example() {
+        let (seed_ranges, tables) = example();
+        let highest_progress = AtomicU64::new(0);
+
+        let minimum = scan_seed_ranges(&seed_ranges, &tables, 5, |done, total| {
+            assert!(done <= total);
+            highest_progress.fetch_max(done, Ordering::Relaxed);
+        });
+
+        assert_eq!(minimum, Some(46));
+        assert_eq!(highest_progress.load(Ordering::Relaxed), 14 + 13);
+        assert_eq!(scan_seed_ranges(&[], &tables, 5, |_, _| {}), None);
+    }
+
+    #[test]
+    fn test_sample_seeds() {
+        let (large, small) = (0..101, 10..13);
+
+        let samples = |ranges: &[Range<u128>], samples_per_range| sample_seeds(ranges, samples_per_range).collect::<Vec<_>>();
+
+        assert_eq!(samples(&[large], 5), vec![0, 25, 50, 75, 100]);
+        assert_eq!(samples(std::slice::from_ref(&small), 5), vec![10, 11, 12]);
+        assert_eq!(samples(std::slice::from_ref(&small), 1), vec![10]);
+        assert!(samples(&[small], 0).is_empty());
+        let uneven = 0..11;
+        assert_eq!(samples(std::slice::from_ref(&uneven), 4), vec![0, 3, 6, 10]);
+
+        // i * (length - 1) overflows here, and collecting every seed would never finish
+        let huge = 0..u128::MAX;
+        assert_eq!(samples(std::slice::from_ref(&huge), 3), vec![0, u128::MAX / 2, u128::MAX - 1]);
+        assert_eq!(sample_seeds(&[huge], u64::MAX).take(2).collect::<Vec<_>>(), vec![0, (u128::MAX - 1) / (u64::MAX as u128 - 1)]);
+    }
+
+    #[test]
+    fn test_verify_example() {
+        let (seed_ranges, tables) = example();
+
+        assert!(verify_samples(&seed_ranges, &tables, 4).is_empty());
+        assert!(verify_samples(&seed_ranges, &tables, 100).is_empty());
+    }
+}
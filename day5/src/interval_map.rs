@@ -0,0 +1,306 @@
+use std::ops::Range;
+
+use super::{Mapping, MappingEntry};
+
+// Values are kept below i128::MAX so offsets can always be applied with signed arithmetic
+pub const DOMAIN_END: u128 = i128::MAX as u128;
+
+// Every value in the source range is moved by the offset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piece {
+    pub source: Range<u128>,
+    pub offset: i128,
+}
+
+impl Piece {
+    fn target(&self) -> Range<u128> {
+        shift(self.source.start, self.offset)..shift(self.source.end, self.offset)
+    }
+}
+
+fn shift(value: u128, offset: i128) -> u128 {
+    (value as i128 + offset) as u128
+}
+
+// Sorts the intervals and merges any that overlap or touch, dropping empty ones
+pub fn normalize_intervals(mut intervals: Vec<Range<u128>>) -> Vec<Range<u128>> {
+    intervals.retain(|interval| interval.start < interval.end);
+    intervals.sort_by_key(|interval| interval.start);
+
+    let mut normalized: Vec<Range<u128>> = Vec::new();
+    for interval in intervals {
+        match normalized.last_mut() {
+            Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+            _ => normalized.push(interval),
+        }
+    }
+
+    normalized
+}
+
+// A piecewise linear map over 0..DOMAIN_END. Values outside of every piece map to themselves,
+// just like values that aren't covered by any entry of an almanac mapping.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalMap {
+    // Sorted by source start, never overlapping, and never containing an offset of zero once normalized
+    pieces: Vec<Piece>,
+}
+
+impl IntervalMap {
+    pub fn identity() -> IntervalMap {
+        IntervalMap { pieces: Vec::new() }
+    }
+
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    // Builds the map for a single almanac table. When entries overlap, the first one wins,
+    // matching map_source_to_target.
+    pub fn from_mapping(mapping: &Mapping) -> IntervalMap {
+        let mut pieces: Vec<Piece> = Vec::new();
+
+        for entry in &mapping.ranges {
+            let offset = entry.target_range.start as i128 - entry.source_range.start as i128;
+
+            // Only the parts of this entry that no earlier entry covers yet
+            let mut uncovered = vec![entry.source_range.clone()];
+            for piece in &pieces {
+                uncovered = uncovered
+                    .into_iter()
+                    .flat_map(|range| {
+                        vec![
+                            range.start..range.end.min(piece.source.start),
+                            range.start.max(piece.source.end)..range.end,
+                        ]
+                    })
+                    .filter(|range| range.start < range.end)
+                    .collect();
+            }
+
+            pieces.extend(uncovered.into_iter().map(|source| Piece { source, offset }));
+        }
+
+        IntervalMap::from_pieces(pieces)
+    }
+
+    // Turns the map back into an almanac table, with one entry per piece
+    pub fn to_mapping(&self, source_name: &str, target_name: &str) -> Mapping {
+        let ranges = self.pieces.iter()
+            .map(|piece| MappingEntry {
+                source_range: piece.source.clone(),
+                target_range: piece.target(),
+                length: piece.source.end - piece.source.start,
+            })
+            .collect::<Vec<_>>();
+
+        Mapping {
+            source_name: source_name.to_string(),
+            target_name: target_name.to_string(),
+            ranges,
+        }
+    }
+
+    // Composes every table of the pipeline, in order, into a single map
+    pub fn from_pipeline(tables: &[Mapping]) -> IntervalMap {
+        tables.iter().fold(IntervalMap::identity(), |map, table| map.compose(&IntervalMap::from_mapping(table)))
+    }
+
+    // Pieces must not overlap
+    pub fn from_pieces(mut pieces: Vec<Piece>) -> IntervalMap {
+        pieces.sort_by_key(|piece| piece.source.start);
+        let mut map = IntervalMap { pieces };
+        map.normalize();
+        map
+    }
+
+    // Drops empty and identity pieces and merges neighbouring pieces that move values by the same offset
+    pub fn normalize(&mut self) {
+        let mut normalized: Vec<Piece> = Vec::new();
+
+        for piece in self.pieces.drain(..) {
+            if piece.offset == 0 || piece.source.start >= piece.source.end {
+                continue;
+            }
+
+            match normalized.last_mut() {
+                Some(last) if last.source.end == piece.source.start && last.offset == piece.offset => {
+                    last.source.end = piece.source.end;
+                }
+                _ => normalized.push(piece),
+            }
+        }
+
+        self.pieces = normalized;
+    }
+
+    // Every piece plus the identity gaps between them, covering the whole domain in order
+    fn segments(&self) -> Vec<Piece> {
+        let mut segments = Vec::new();
+        let mut position = 0;
+
+        for piece in &self.pieces {
+            if position < piece.source.start {
+                segments.push(Piece { source: position..piece.source.start, offset: 0 });
+            }
+            segments.push(piece.clone());
+            position = piece.source.end;
+        }
+
+        if position < DOMAIN_END {
+            segments.push(Piece { source: position..DOMAIN_END, offset: 0 });
+        }
+
+        segments
+    }
+
+    pub fn apply(&self, value: u128) -> u128 {
+        // Pieces are sorted, so the candidate is the last piece starting at or before the value
+        let index = self.pieces.partition_point(|piece| piece.source.start <= value);
+
+        match index.checked_sub(1).map(|index| &self.pieces[index]) {
+            Some(piece) if piece.source.contains(&value) => shift(value, piece.offset),
+            _ => value,
+        }
+    }
+
+    // Maps a set of intervals, splitting them wherever they cross a piece boundary
+    pub fn apply_to_intervals(&self, intervals: &[Range<u128>]) -> Vec<Range<u128>> {
+        let mut mapped = Vec::new();
+
+        for interval in intervals {
+            for segment in self.segments() {
+                let start = interval.start.max(segment.source.start);
+                let end = interval.end.min(segment.source.end);
+
+                if start < end {
+                    mapped.push(shift(start, segment.offset)..shift(end, segment.offset));
+                }
+            }
+        }
+
+        normalize_intervals(mapped)
+    }
+
+    // Every value that the map sends into one of the intervals. The map isn't necessarily injective,
+    // so a single target interval can come from several source intervals.
+    pub fn preimage(&self, intervals: &[Range<u128>]) -> Vec<Range<u128>> {
+        let mut sources = Vec::new();
+
+        for segment in self.segments() {
+            let target = segment.target();
+
+            for interval in intervals {
+                let start = target.start.max(interval.start);
+                let end = target.end.min(interval.end);
+
+                if start < end {
+                    sources.push(shift(start, -segment.offset)..shift(end, -segment.offset));
+                }
+            }
+        }
+
+        normalize_intervals(sources)
+    }
+
+    // The map that applies self first and then the other map
+    pub fn compose(&self, then: &IntervalMap) -> IntervalMap {
+        let then_segments = then.segments();
+        let mut pieces = Vec::new();
+
+        for segment in self.segments() {
+            let target = segment.target();
+
+            for then_segment in &then_segments {
+                let start = target.start.max(then_segment.source.start);
+                let end = target.end.min(then_segment.source.end);
+
+                if start < end {
+                    // Back into the source space of self
+                    let source = shift(start, -segment.offset)..shift(end, -segment.offset);
+                    pieces.push(Piece { source, offset: segment.offset + then_segment.offset });
+                }
+            }
+        }
+
+        IntervalMap::from_pieces(pieces)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_section, run_translation_pipeline, split_sections};
+
+    fn example_tables() -> Vec<Mapping> {
+        split_sections(include_str!("./example.txt"))[1..].iter().map(|s| parse_section(s)).collect()
+    }
+
+    #[test]
+    fn test_from_mapping_first_entry_wins() {
+        let mapping = parse_section("a-to-b map:\n100 0 10\n200 5 10");
+        let map = IntervalMap::from_mapping(&mapping);
+
+        assert_eq!(map.pieces(), &[
+            Piece { source: 0..10, offset: 100 },
+            Piece { source: 10..15, offset: 195 },
+        ]);
+        assert_eq!(map.apply(7), 107);
+        assert_eq!(map.apply(12), 207);
+        assert_eq!(map.apply(15), 15);
+    }
+
+    #[test]
+    fn test_normalize_merges_pieces() {
+        let map = IntervalMap::from_pieces(vec![
+            Piece { source: 10..20, offset: 5 },
+            Piece { source: 0..10, offset: 5 },
+            Piece { source: 20..30, offset: 0 },
+        ]);
+
+        assert_eq!(map.pieces(), &[Piece { source: 0..20, offset: 5 }]);
+    }
+
+    #[test]
+    fn test_composed_pipeline_matches_step_by_step() {
+        let tables = example_tables();
+        let composed = IntervalMap::from_pipeline(&tables);
+
+        for seed in 0..120 {
+            assert_eq!(composed.apply(seed), run_translation_pipeline(seed, &tables), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_compose_is_associative() {
+        let maps = example_tables().iter().map(IntervalMap::from_mapping).collect::<Vec<_>>();
+
+        let left = maps[0].compose(&maps[1]).compose(&maps[2]);
+        let right = maps[0].compose(&maps[1].compose(&maps[2]));
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_apply_to_intervals() {
+        let composed = IntervalMap::from_pipeline(&example_tables());
+        let locations = composed.apply_to_intervals(&[79..93, 55..68]);
+
+        assert_eq!(locations.iter().map(|range| range.start).min(), Some(46));
+
+        // Every seed has to land inside one of the resulting intervals
+        for seed in (79..93).chain(55..68) {
+            let location = composed.apply(seed);
+            assert!(locations.iter().any(|range| range.contains(&location)));
+        }
+
+        let total_length: u128 = locations.iter().map(|range| range.end - range.start).sum();
+        assert_eq!(total_length, 14 + 13);
+    }
+
+    #[test]
+    fn test_normalize_intervals() {
+        assert_eq!(normalize_intervals(vec![5..10, 0..3, 3..4, 8..12, 20..20]), vec![0..4, 5..12]);
+    }
+}
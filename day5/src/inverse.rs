@@ -0,0 +1,162 @@
+use std::ops::Range;
+
+use super::{build_translation_pipeline, Mapping, MappingEntry};
+use super::interval_map::{normalize_intervals, IntervalMap};
+
+fn total_length(intervals: &[Range<u128>]) -> u128 {
+    intervals.iter().map(|interval| interval.end - interval.start).sum()
+}
+
+// Swaps the direction of a table, so the result maps targets back to sources.
+// This only works when the table is a bijection: the entries may not overlap on either side, and
+// they have to cover the same values on both sides, otherwise some values that aren't covered by
+// any entry would collide with values that are moved onto them.
+pub fn invert_mapping(mapping: &Mapping) -> Result<Mapping, &'static str> {
+    let sources = mapping.ranges.iter().map(|entry| entry.source_range.clone()).collect::<Vec<_>>();
+    let targets = mapping.ranges.iter().map(|entry| entry.target_range.clone()).collect::<Vec<_>>();
+
+    let merged_sources = normalize_intervals(sources.clone());
+    let merged_targets = normalize_intervals(targets.clone());
+
+    if total_length(&merged_sources) != total_length(&sources) {
+        return Err("Mapping has overlapping source ranges");
+    }
+
+    if total_length(&merged_targets) != total_length(&targets) {
+        return Err("Mapping has overlapping target ranges");
+    }
+
+    if merged_sources != merged_targets {
+        return Err("Mapping doesn't cover the same values on both sides");
+    }
+
+    let ranges = mapping.ranges.iter()
+        .map(|entry| MappingEntry {
+            source_range: entry.target_range.clone(),
+            target_range: entry.source_range.clone(),
+            length: entry.length,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Mapping {
+        source_name: mapping.target_name.clone(),
+        target_name: mapping.source_name.clone(),
+        ranges,
+    })
+}
+
+// Every range of seeds that ends up somewhere in the given location ranges. Tables don't have to be
+// invertible: when a table can't be inverted, it's undone by taking the preimage of the ranges found so far.
+pub fn seeds_for_locations(tables: &[Mapping], locations: &[Range<u128>]) -> Result<Vec<Range<u128>>, &'static str> {
+    let pipeline = build_translation_pipeline("location", "seed", tables, true)?;
+
+    let seeds = pipeline.iter().fold(normalize_intervals(locations.to_vec()), |ranges, table| {
+        match invert_mapping(table) {
+            Ok(inverse) => IntervalMap::from_mapping(&inverse).apply_to_intervals(&ranges),
+            Err(_) => IntervalMap::from_mapping(table).preimage(&ranges),
+        }
+    });
+
+    Ok(seeds)
+}
+
+// Accepts a single location, or a range written as start..end with an exclusive end
+pub fn parse_location_query(query: &str) -> Result<Range<u128>, &'static str> {
+    let parse = |value: &str| value.trim().parse::<u128>().map_err(|_| "Invalid location");
+
+    match query.split_once("..") {
+        Some((start, end)) => {
+            let range = parse(start)?..parse(end)?;
+            if range.start >= range.end {
+                return Err("Location range is empty");
+            }
+            Ok(range)
+        }
+        None => {
+            let location = parse(query)?;
+            Ok(location..location + 1)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{map_source_to_target, parse_section, run_translation_pipeline, split_sections};
+
+    fn example_tables() -> Vec<Mapping> {
+        split_sections(include_str!("./example.txt"))[1..].iter().map(|s| parse_section(s)).collect()
+    }
+
+    #[test]
+    fn test_invert_mapping() {
+        let mapping = parse_section("seed-to-soil map:\n50 98 2\n52 50 48");
+        let inverse = invert_mapping(&mapping).unwrap();
+
+        assert_eq!(inverse.source_name, "soil");
+        assert_eq!(inverse.target_name, "seed");
+
+        for seed in 0..120 {
+            assert_eq!(map_source_to_target(map_source_to_target(seed, &mapping), &inverse), seed);
+        }
+    }
+
+    #[test]
+    fn test_preimage_matches_inverted_mapping() {
+        let mapping = parse_section("seed-to-soil map:\n50 98 2\n52 50 48");
+        let inverse = invert_mapping(&mapping).unwrap();
+        let locations = 40..99;
+
+        assert_eq!(
+            IntervalMap::from_mapping(&mapping).preimage(std::slice::from_ref(&locations)),
+            IntervalMap::from_mapping(&inverse).apply_to_intervals(&[locations]),
+        );
+    }
+
+    #[test]
+    fn test_invert_mapping_not_bijective() {
+        assert!(invert_mapping(&parse_section("a-to-b map:\n0 10 5\n2 20 5")).is_err());
+        assert!(invert_mapping(&parse_section("a-to-b map:\n0 10 5\n20 12 5")).is_err());
+        assert!(invert_mapping(&parse_section("a-to-b map:\n0 10 5")).is_err());
+    }
+
+    #[test]
+    fn test_seeds_for_location() {
+        let tables = example_tables();
+
+        // The lowest location of part 2 comes from seed 82
+        let seeds = seeds_for_locations(&tables, &[parse_location_query("46").unwrap()]).unwrap();
+        assert!(seeds.iter().any(|range| range.contains(&82)));
+
+        let pipeline = build_translation_pipeline("seed", "location", &tables, false).unwrap();
+        for range in &seeds {
+            for seed in range.clone() {
+                assert_eq!(run_translation_pipeline(seed, &pipeline), 46);
+            }
+        }
+    }
+
+    #[test]
+    fn test_seeds_for_locations_matches_forward_pass() {
+        let tables = example_tables();
+        let pipeline = build_translation_pipeline("seed", "location", &tables, false).unwrap();
+
+        let locations = 40..60;
+        let seeds = seeds_for_locations(&tables, std::slice::from_ref(&locations)).unwrap();
+
+        for seed in 0..200 {
+            let location = run_translation_pipeline(seed, &pipeline);
+            let found = seeds.iter().any(|range| range.contains(&seed));
+            assert_eq!(found, locations.contains(&location), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_parse_location_query() {
+        assert_eq!(parse_location_query("46"), Ok(46..47));
+        assert_eq!(parse_location_query("40..60"), Ok(40..60));
+        assert!(parse_location_query("60..40").is_err());
+        assert!(parse_location_query("abc").is_err());
+    }
+}
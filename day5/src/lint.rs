@@ -0,0 +1,279 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+
+        write!(f, "line {}: {}: {}", self.line, severity, self.message)
+    }
+}
+
+struct LintEntry {
+    line: usize,
+    source_range: Range<u128>,
+    target_range: Range<u128>,
+}
+
+struct LintTable {
+    line: usize,
+    source_name: String,
+    target_name: String,
+    entries: Vec<LintEntry>,
+}
+
+fn diagnostic(line: usize, severity: Severity, message: String) -> Diagnostic {
+    Diagnostic { line, severity, message }
+}
+
+fn overlap(a: &Range<u128>, b: &Range<u128>) -> Option<Range<u128>> {
+    let start = a.start.max(b.start);
+    let end = a.end.min(b.end);
+
+    if start < end {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+// Splits the almanac into tables while keeping track of line numbers, which parse_section
+// doesn't know about. Lines that can't be parsed are reported as errors and skipped.
+fn parse_tables(input: &str, diagnostics: &mut Vec<Diagnostic>) -> Vec<LintTable> {
+    let mut tables: Vec<LintTable> = Vec::new();
+    let mut in_table = false;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+
+        if line.is_empty() {
+            in_table = false;
+            continue;
+        }
+
+        if line.starts_with("seeds:") {
+            continue;
+        }
+
+        if let Some(name) = line.strip_suffix(" map:") {
+            match name.split_once("-to-") {
+                Some((source_name, target_name)) if !source_name.is_empty() && !target_name.is_empty() => {
+                    tables.push(LintTable {
+                        line: line_number,
+                        source_name: source_name.to_string(),
+                        target_name: target_name.to_string(),
+                        entries: Vec::new(),
+                    });
+                    in_table = true;
+                }
+                _ => diagnostics.push(diagnostic(line_number, Severity::Error, format!("invalid map name '{}'", name))),
+            }
+            continue;
+        }
+
+        if !in_table {
+            diagnostics.push(diagnostic(line_number, Severity::Error, "entry outside of a map".to_string()));
+            continue;
+        }
+
+        let numbers = line.split_whitespace().map(|part| part.parse::<u128>()).collect::<Result<Vec<_>, _>>();
+        match numbers.as_deref() {
+            Ok([target_start, source_start, length]) => match (source_start.checked_add(*length), target_start.checked_add(*length)) {
+                (Some(source_end), Some(target_end)) => {
+                    tables.last_mut().unwrap().entries.push(LintEntry {
+                        line: line_number,
+                        source_range: *source_start..source_end,
+                        target_range: *target_start..target_end,
+                    });
+                }
+                _ => diagnostics.push(diagnostic(line_number, Severity::Error, format!("entry runs past the largest value of {}", u128::MAX))),
+            },
+            _ => diagnostics.push(diagnostic(line_number, Severity::Error, "expected three numbers: target start, source start and length".to_string())),
+        }
+    }
+
+    tables
+}
+
+fn lint_table(table: &LintTable, diagnostics: &mut Vec<Diagnostic>) {
+    let name = format!("{}-to-{}", table.source_name, table.target_name);
+
+    if table.entries.is_empty() {
+        diagnostics.push(diagnostic(table.line, Severity::Warning, format!("{} has no entries", name)));
+    }
+
+    for (i, entry) in table.entries.iter().enumerate() {
+        if entry.source_range.is_empty() {
+            diagnostics.push(diagnostic(entry.line, Severity::Warning, format!("zero-length entry in {}", name)));
+            continue;
+        }
+
+        for earlier in &table.entries[..i] {
+            if let Some(range) = overlap(&earlier.source_range, &entry.source_range) {
+                diagnostics.push(diagnostic(entry.line, Severity::Warning, format!(
+                    "source values {}..{} are already mapped by line {}, so this entry is ignored for them",
+                    range.start, range.end, earlier.line,
+                )));
+            }
+
+            if let Some(range) = overlap(&earlier.target_range, &entry.target_range) {
+                diagnostics.push(diagnostic(entry.line, Severity::Warning, format!(
+                    "target values {}..{} are also produced by line {}, so {} isn't injective",
+                    range.start, range.end, earlier.line, name,
+                )));
+            }
+        }
+    }
+
+    // Gaps between the entries, which map every value to itself
+    let mut entries = table.entries.iter().filter(|entry| !entry.source_range.is_empty()).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.source_range.start);
+
+    let mut covered_until: Option<u128> = None;
+    for entry in entries {
+        if let Some(end) = covered_until {
+            if end < entry.source_range.start {
+                diagnostics.push(diagnostic(entry.line, Severity::Note, format!(
+                    "source values {}..{} aren't covered by any entry of {} and map to themselves",
+                    end, entry.source_range.start, name,
+                )));
+            }
+        }
+
+        covered_until = Some(covered_until.map_or(entry.source_range.end, |end| end.max(entry.source_range.end)));
+    }
+}
+
+// Maps whose source category can't be reached from seeds by following the other maps
+fn lint_reachability(tables: &[LintTable], diagnostics: &mut Vec<Diagnostic>) {
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut queue: VecDeque<&str> = VecDeque::new();
+    reached.insert("seed");
+    queue.push_back("seed");
+
+    while let Some(category) = queue.pop_front() {
+        for table in tables.iter().filter(|table| table.source_name == category) {
+            if reached.insert(&table.target_name) {
+                queue.push_back(&table.target_name);
+            }
+        }
+    }
+
+    for table in tables.iter().filter(|table| !reached.contains(table.source_name.as_str())) {
+        diagnostics.push(diagnostic(table.line, Severity::Warning, format!("category '{}' is never reached from seed", table.source_name)));
+    }
+}
+
+// Checks the whole almanac without running anything, sorted by line
+pub fn lint_almanac(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if !input.lines().any(|line| line.trim().starts_with("seeds:")) {
+        diagnostics.push(diagnostic(1, Severity::Error, "missing seeds line".to_string()));
+    }
+
+    let tables = parse_tables(input, &mut diagnostics);
+
+    for table in &tables {
+        lint_table(table, &mut diagnostics);
+    }
+
+    lint_reachability(&tables, &mut diagnostics);
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    diagnostics
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(input: &str) -> Vec<(usize, Severity)> {
+        lint_almanac(input).iter().map(|diagnostic| (diagnostic.line, diagnostic.severity)).collect()
+    }
+
+    #[test]
+    fn test_lint_example_has_no_problems() {
+        let diagnostics = lint_almanac(include_str!("./example.txt"));
+
+        assert!(!has_errors(&diagnostics));
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic.severity == Severity::Note));
+    }
+
+    #[test]
+    fn test_lint_overlaps() {
+        let input = "seeds: 1 2\n\nseed-to-soil map:\n100 0 10\n200 5 10\n105 20 3";
+        let diagnostics = lint_almanac(input);
+
+        assert_eq!(messages(input), vec![(5, Severity::Warning), (6, Severity::Warning), (6, Severity::Note)]);
+        assert!(diagnostics[0].message.contains("5..10"));
+        assert!(diagnostics[0].message.contains("line 4"));
+        assert!(diagnostics[1].message.contains("105..108"));
+        assert!(diagnostics[2].message.contains("15..20"));
+    }
+
+    #[test]
+    fn test_lint_zero_length_and_unreachable() {
+        let input = "seeds: 1 2\n\nseed-to-soil map:\n1 2 0\n\nwater-to-light map:\n1 2 3";
+
+        assert_eq!(messages(input), vec![(4, Severity::Warning), (6, Severity::Warning)]);
+    }
+
+    #[test]
+    fn test_lint_errors() {
+        let input = "seed-to-soil map:\n1 2\n\n3 4 5\n\nseedsoil map:\n1 2 3";
+        let diagnostics = lint_almanac(input);
+
+        assert!(has_errors(&diagnostics));
+        assert_eq!(messages(input), vec![
+            (1, Severity::Error),
+            (1, Severity::Warning),
+            (2, Severity::Error),
+            (4, Severity::Error),
+            (6, Severity::Error),
+            (7, Severity::Error),
+        ]);
+    }
+
+    #[test]
+    fn test_lint_overflowing_entry() {
+        let input = format!("seeds: 1 2\n\nseed-to-soil map:\n1 {} 2\n{} 1 2\n0 0 5", u128::MAX - 1, u128::MAX);
+        let diagnostics = lint_almanac(&input);
+
+        assert!(has_errors(&diagnostics));
+        assert_eq!(messages(&input), vec![(4, Severity::Error), (5, Severity::Error)]);
+        assert!(diagnostics[0].message.contains("runs past the largest value"));
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = diagnostic(3, Severity::Warning, "something".to_string());
+
+        assert_eq!(diagnostic.to_string(), "line 3: warning: something");
+    }
+}
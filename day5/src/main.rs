@@ -1,15 +1,25 @@
 use std::ops::Range;
-use rayon::prelude::*;
 
+mod brute_force;
+mod interval_map;
+mod inverse;
+mod lint;
+mod provenance;
+mod routing;
+mod serialize;
 
-#[derive(Clone)]
+use interval_map::IntervalMap;
+use routing::CategoryGraph;
+
+
+#[derive(Debug, Clone, PartialEq)]
 struct MappingEntry {
     source_range: Range<u128>,
     target_range: Range<u128>,
     length: u128,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct Mapping {
     source_name: String,
     target_name: String,
@@ -29,33 +39,18 @@ fn run_translation_pipeline(start_source: u128, tables: &Vec<Mapping>) -> u128 {
     target
 }
 
-fn build_translation_pipeline(start_source: &str, end_target: &str, tables: &Vec<Mapping>, reverse: bool) -> Vec<Mapping> {
-    let mut pipeline = Vec::new();
-
-    let mut current_target = start_source;
+// The chain of tables converting start_source into end_target. With reverse set, the chain is walked
+// backwards from start_source, so the tables come out in the order needed to undo the conversion.
+fn build_translation_pipeline(start_source: &str, end_target: &str, tables: &[Mapping], reverse: bool) -> Result<Vec<Mapping>, &'static str> {
+    let graph = CategoryGraph::new(tables)?;
 
-    while current_target != end_target {
-        
-        //println!("current source: {}", current_target);
-
-        let table = tables.iter()
-            .find(|t| {
-                if reverse {
-                    t.target_name == current_target
-                } else {
-                    t.source_name == current_target
-                }
-            })
-            .unwrap();
-
-        //println!("target table: {:?}", table.target_name);
-
-        pipeline.push(table.clone());
-
-        current_target = &table.target_name;
+    if reverse {
+        let mut pipeline = graph.route(end_target, start_source)?;
+        pipeline.reverse();
+        Ok(pipeline)
+    } else {
+        graph.route(start_source, end_target)
     }
-
-    pipeline
 }
 
 fn map_source_to_target(source: u128, table: &Mapping) -> u128 {
@@ -72,10 +67,11 @@ fn map_source_to_target(source: u128, table: &Mapping) -> u128 {
     target
 }
 
+// The seed list may be empty, which leaves nothing after the colon
 fn parse_seeds_single(line: &str) -> Vec<u128> {
     let seeds = line
-        .split(": ").nth(1).unwrap()
-        .split(" ")
+        .split(':').nth(1).unwrap()
+        .split_whitespace()
         .map(|s| s.parse::<u128>().unwrap())
         .collect::<Vec<_>>();
 
@@ -84,14 +80,14 @@ fn parse_seeds_single(line: &str) -> Vec<u128> {
 
 fn parse_seeds_ranges(line: &str) -> Vec<(u128, u128)> {
     let seeds = line
-        .split(": ").nth(1).unwrap()
-        .split(" ")
+        .split(':').nth(1).unwrap()
+        .split_whitespace()
         .collect::<Vec<_>>()
         .chunks(2)
         .map(|s| (s[0].parse::<u128>().unwrap(), s[1].parse::<u128>().unwrap() - 1))
         .collect::<Vec<_>>();
 
-    println!("{:?}", seeds);
+    //println!("{:?}", seeds);
 
     seeds
 }
@@ -116,7 +112,7 @@ fn parse_section(section: &str) -> Mapping {
         target_name = name_parts[1];
     }
 
-    println!("{} {}", source_name, target_name);
+    //println!("{} {}", source_name, target_name);
 
 
     let mut ranges = Vec::new();
@@ -153,9 +149,23 @@ fn parse_section(section: &str) -> Mapping {
     }
 }
 
+// Sections are separated by a blank line, with either Windows or Unix line endings
+fn split_sections(input: &str) -> Vec<&str> {
+    let separator = if input.contains("\r\n\r\n") { "\r\n\r\n" } else { "\n\n" };
+
+    input.split(separator)
+        .map(|section| section.trim_end())
+        .filter(|section| !section.is_empty())
+        .collect::<Vec<_>>()
+}
+
+fn load_raw_input() -> &'static str {
+    include_str!("./input.txt")
+}
+
 fn load_input() -> Vec<&'static str> {
-    let input = include_str!("./input.txt");
-    let sections = input.split("\r\n\r\n").collect::<Vec<_>>();
+    let input = load_raw_input();
+    let sections = split_sections(input);
 
     //println!("{:?} {:?}", sections, sections.len());
 
@@ -163,7 +173,7 @@ fn load_input() -> Vec<&'static str> {
 }
 
 fn translate_seeds_to_location(seeds: Vec<u128>, tables: &Vec<Mapping>) -> Vec<u128> {
-    let pipeline = build_translation_pipeline("seed", "location", &tables, false);
+    let pipeline = build_translation_pipeline("seed", "location", &tables, false).expect("No route from seed to location");
 
     let seed_locations = seeds.iter()
         .map(|s| run_translation_pipeline(*s, &pipeline))
@@ -182,62 +192,56 @@ fn part1(seed_section: &str, maps: &Vec<Mapping>) -> u128 {
     lowest_seed_location.clone()
 }
 
-// Shamelessly adapted from another solution after I got stuck in a implicit bug in my code
-fn part2(seed_section: &str, maps: &Vec<Mapping>) -> u128 {
+fn seed_ranges(seed_section: &str) -> Vec<Range<u128>> {
     let seeds = parse_seeds_ranges(seed_section);
 
-    let mut source_ranges = Vec::new();
-
-    for seed_range in seeds {
-        source_ranges.push((seed_range.0, seed_range.0 + seed_range.1 - 1));
-    }
-
-    for category_map in maps.iter() {
-        let mut final_range = Vec::new();
+    // parse_seeds_ranges gives the length minus one
+    seeds.iter()
+        .map(|(start, length)| *start..*start + *length + 1)
+        .collect::<Vec<_>>()
+}
 
-        'iterate_ranges: while let Some(source_range) = source_ranges.pop() {
-            for map_line in category_map.ranges.iter() {
-                let line_source_start = map_line.source_range.start;
-                let line_source_end = map_line.source_range.start + map_line.length - 1;
+fn part2(seed_section: &str, maps: &Vec<Mapping>) -> u128 {
+    let seed_ranges = seed_ranges(seed_section);
 
-                let line_destination_start = map_line.target_range.start;
+    // Compose every table into a single map, so the seed ranges only have to be split once
+    let pipeline = IntervalMap::from_pipeline(maps);
+    let location_ranges = pipeline.apply_to_intervals(&seed_ranges);
 
-                if line_source_start <= source_range.1 && line_source_end >= source_range.0 {
-                    if source_range.0 < line_source_start {
-                        source_ranges.push((source_range.0, line_source_start - 1));
-                    }
+    let minimum = location_ranges
+        .iter()
+        .map(|range| range.start)
+        .min()
+        .unwrap();
 
-                    if source_range.1 > line_source_end {
-                        source_ranges.push((line_source_end + 1, source_range.1));
-                    }
+    minimum
+}
 
-                    final_range.push((
-                        u128::max(line_source_start, source_range.0) - line_source_start
-                            + line_destination_start,
-                        u128::min(line_source_end, source_range.1) - line_source_start
-                            + line_destination_start,
-                    ));
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("Usage: day5 [--lint | --seeds-for <location | start..end> | --paths <from> <to> | --convert <from> <to> <value> | --trace | --normalize | --compose | --json | --brute-force | --verify <samples>]");
+    std::process::exit(1);
+}
 
-                    continue 'iterate_ranges;
-                }
-            }
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
 
-            final_range.push((source_range.0, source_range.1));
-        }
+    // Validate the almanac before anything runs. day5 --lint also prints the notes about gaps and stops there.
+    let lint_only = args.iter().any(|arg| arg == "--lint");
+    let diagnostics = lint::lint_almanac(load_raw_input());
 
-        source_ranges = final_range;
+    for diagnostic in diagnostics.iter().filter(|diagnostic| lint_only || diagnostic.severity != lint::Severity::Note) {
+        eprintln!("{}", diagnostic);
     }
 
-    let minimum = source_ranges
-        .iter()
-        .map(|(range_min, _)| *range_min)
-        .min()
-        .unwrap();
+    if lint::has_errors(&diagnostics) {
+        std::process::exit(1);
+    }
 
-    minimum
-}
+    if lint_only {
+        return;
+    }
 
-fn main() {
     let sections = load_input();
 
     let seed_section = sections[0];
@@ -245,7 +249,96 @@ fn main() {
         .map(|s| parse_section(s))
         .collect::<Vec<_>>();
 
-    let pipeline = build_translation_pipeline("seed", "location", &tables, false);
+    match args.as_slice() {
+        // day5 --seeds-for <location | start..end> lists the seed ranges that end up in those locations
+        [flag, query] if flag == "--seeds-for" => {
+            let locations = inverse::parse_location_query(query).unwrap_or_else(|error| exit_with_error(error));
+            let seeds = inverse::seeds_for_locations(&tables, &[locations]).unwrap_or_else(|error| exit_with_error(error));
+
+            for range in seeds {
+                println!("{}..{}", range.start, range.end);
+            }
+            return;
+        }
+        // day5 --paths <from> <to> lists every chain of maps between two categories
+        [flag, from, to] if flag == "--paths" => {
+            let graph = CategoryGraph::new(&tables).unwrap_or_else(|error| exit_with_error(error));
+
+            for path in graph.paths(from, to).unwrap_or_else(|error| exit_with_error(error)) {
+                println!("{}", routing::describe_path(from, &path));
+            }
+            return;
+        }
+        // day5 --convert <from> <to> <value> converts a single value along the shortest chain of maps
+        [flag, from, to, value] if flag == "--convert" => {
+            let value = value.parse::<u128>().unwrap_or_else(|_| exit_with_error("Invalid value"));
+            let pipeline = build_translation_pipeline(from, to, &tables, false).unwrap_or_else(|error| exit_with_error(error));
+
+            println!("{}", run_translation_pipeline(value, &pipeline));
+            return;
+        }
+        // day5 --normalize | --compose | --json writes the almanac back out, with tidied up tables,
+        // with the whole seed to location pipeline merged into one table, or as JSON
+        [flag] if flag == "--normalize" || flag == "--compose" || flag == "--json" => {
+            let seeds = parse_seeds_single(seed_section);
+
+            let output = match flag.as_str() {
+                "--normalize" => serialize::almanac_to_text(&seeds, &tables.iter().map(serialize::normalize_mapping).collect::<Vec<_>>()),
+                "--compose" => {
+                    let pipeline = build_translation_pipeline("seed", "location", &tables, false).unwrap_or_else(|error| exit_with_error(error));
+                    let composed = serialize::compose_pipeline(&pipeline).unwrap_or_else(|error| exit_with_error(error));
+                    serialize::almanac_to_text(&seeds, &[composed])
+                }
+                _ => serialize::almanac_to_json(&seeds, &tables),
+            };
+
+            println!("{}", output.trim_end());
+            return;
+        }
+        _ => {}
+    }
+
+    let pipeline = build_translation_pipeline("seed", "location", &tables, false).unwrap_or_else(|error| exit_with_error(error));
+
+    match args.as_slice() {
+        // day5 --brute-force runs every seed of part 2 through the tables on all cores, as a cross-check
+        [flag] if flag == "--brute-force" => {
+            let minimum = brute_force::scan_seed_ranges(&seed_ranges(seed_section), &pipeline, 1_000_000, |done, total| {
+                eprint!("\r{}/{} seeds ({:.1}%)", done, total, done as f64 * 100.0 / total as f64);
+            });
+            eprintln!();
+
+            match minimum {
+                Some(minimum) => println!("Part 2 (brute force): {}", minimum),
+                None => println!("Part 2 (brute force): no seeds"),
+            }
+            println!("Part 2 (intervals): {}", part2(seed_section, &pipeline));
+            return;
+        }
+        // day5 --verify <samples> checks that many seeds of each part 2 range against the interval result
+        [flag, samples] if flag == "--verify" => {
+            let samples = samples.parse::<u64>().unwrap_or_else(|_| exit_with_error("Invalid number of samples"));
+            let mismatches = brute_force::verify_samples(&seed_ranges(seed_section), &pipeline, samples);
+
+            for mismatch in &mismatches {
+                println!("seed {}: expected location {}, intervals gave {}", mismatch.seed, mismatch.expected, mismatch.actual);
+            }
+            println!("{} mismatches", mismatches.len());
+
+            if !mismatches.is_empty() {
+                std::process::exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    // day5 --trace prints every location range of part 2 as JSON, along with the seeds and map entries it came from
+    if args.iter().any(|arg| arg == "--trace") {
+        let traced = provenance::trace_seed_ranges(&seed_ranges(seed_section), &pipeline);
+        println!("{}", provenance::to_json(&traced));
+        return;
+    }
 
     let part1_result = part1(seed_section, &pipeline);
     let part2_result = part2(seed_section, &pipeline);
@@ -304,7 +397,7 @@ mod tests {
             },
         ];
 
-        let pipeline = build_translation_pipeline("seed", "light", &tables, false);
+        let pipeline = build_translation_pipeline("seed", "light", &tables, false).unwrap();
 
         assert_eq!(pipeline.len(), 2);
         assert_eq!(pipeline[0].source_name, "seed");
@@ -346,7 +439,7 @@ mod tests {
     #[test]
     fn test_example_data() {
         let input = include_str!("./example.txt");
-        let sections = input.split("\r\n\r\n").collect::<Vec<_>>();
+        let sections = split_sections(input);
 
         let seed_section = sections[0];
 
@@ -362,7 +455,7 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = include_str!("./input.txt");
-        let sections = input.split("\r\n\r\n").collect::<Vec<_>>();
+        let sections = split_sections(input);
 
         let seed_section = sections[0];
 
@@ -378,7 +471,7 @@ mod tests {
     #[test]
     fn test_part2() {
         let input = include_str!("./input.txt");
-        let sections = input.split("\r\n\r\n").collect::<Vec<_>>();
+        let sections = split_sections(input);
 
         let seed_section = sections[0];
 
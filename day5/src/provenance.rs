@@ -0,0 +1,225 @@
+use std::ops::Range;
+
+use super::{Mapping, MappingEntry};
+
+// One table an interval went through. Values that no entry covers keep their value, and have no entry.
+#[derive(Clone)]
+pub struct Step {
+    pub map: String,
+    pub entry: Option<(usize, MappingEntry)>,
+    pub input: Range<u128>,
+    pub output: Range<u128>,
+}
+
+// A piece of the final location ranges, along with the seeds it came from and how it got there
+pub struct TracedInterval {
+    pub seed_range: Range<u128>,
+    pub seeds: Range<u128>,
+    pub location: Range<u128>,
+    pub steps: Vec<Step>,
+}
+
+// Splits a range by the entries of a table, where the first entry covering a value wins like in
+// map_source_to_target. Each piece comes with the index of the entry covering it.
+fn split_by_table(range: Range<u128>, table: &Mapping) -> Vec<(Range<u128>, Option<usize>)> {
+    let mut pieces = Vec::new();
+    let mut uncovered = vec![range];
+
+    for (index, entry) in table.ranges.iter().enumerate() {
+        let mut remaining = Vec::new();
+
+        for range in uncovered {
+            let start = range.start.max(entry.source_range.start);
+            let end = range.end.min(entry.source_range.end);
+
+            if start < end {
+                pieces.push((start..end, Some(index)));
+                remaining.push(range.start..start);
+                remaining.push(end..range.end);
+            } else {
+                remaining.push(range);
+            }
+        }
+
+        uncovered = remaining.into_iter().filter(|range| range.start < range.end).collect();
+    }
+
+    pieces.extend(uncovered.into_iter().map(|range| (range, None)));
+    pieces
+}
+
+// Runs the seed ranges through the pipeline one table at a time, keeping track of every entry used.
+// The result is sorted by location.
+pub fn trace_seed_ranges(seed_ranges: &[Range<u128>], pipeline: &[Mapping]) -> Vec<TracedInterval> {
+    let mut traced = Vec::new();
+
+    for seed_range in seed_ranges.iter().filter(|range| range.start < range.end) {
+        // The current range, how far it has moved from the seeds so far, and the steps it took
+        let mut current: Vec<(Range<u128>, i128, Vec<Step>)> = vec![(seed_range.clone(), 0, Vec::new())];
+
+        for table in pipeline {
+            let map = format!("{}-to-{}", table.source_name, table.target_name);
+            let mut next = Vec::new();
+
+            for (range, moved, steps) in current {
+                for (input, index) in split_by_table(range, table) {
+                    let entry = index.map(|index| (index, table.ranges[index].clone()));
+                    let offset = entry.as_ref().map_or(0, |(_, entry)| entry.target_range.start as i128 - entry.source_range.start as i128);
+
+                    let output = (input.start as i128 + offset) as u128..(input.end as i128 + offset) as u128;
+
+                    let mut steps = steps.clone();
+                    steps.push(Step { map: map.clone(), entry, input, output: output.clone() });
+                    next.push((output, moved + offset, steps));
+                }
+            }
+
+            current = next;
+        }
+
+        for (location, moved, steps) in current {
+            let seeds = (location.start as i128 - moved) as u128..(location.end as i128 - moved) as u128;
+
+            traced.push(TracedInterval { seed_range: seed_range.clone(), seeds, location, steps });
+        }
+    }
+
+    traced.sort_by_key(|interval| interval.location.start);
+    traced
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+fn json_range(range: &Range<u128>) -> String {
+    format!("{{\"start\": {}, \"end\": {}}}", range.start, range.end)
+}
+
+fn json_step(step: &Step) -> String {
+    let entry = match &step.entry {
+        Some((index, entry)) => format!(
+            "{{\"index\": {}, \"source\": {}, \"target\": {}, \"length\": {}}}",
+            index, json_range(&entry.source_range), json_range(&entry.target_range), entry.length,
+        ),
+        None => "null".to_string(),
+    };
+
+    format!(
+        "{{\"map\": {}, \"entry\": {}, \"input\": {}, \"output\": {}}}",
+        json_string(&step.map), entry, json_range(&step.input), json_range(&step.output),
+    )
+}
+
+// Ranges are written with an exclusive end, and an entry of null means no entry covered the values
+pub fn to_json(traced: &[TracedInterval]) -> String {
+    let intervals = traced.iter()
+        .map(|interval| {
+            let steps = interval.steps.iter()
+                .map(|step| format!("      {}", json_step(step)))
+                .collect::<Vec<_>>()
+                .join(",\n");
+
+            format!(
+                "  {{\n    \"seed_range\": {},\n    \"seeds\": {},\n    \"location\": {},\n    \"steps\": [\n{}\n    ]\n  }}",
+                json_range(&interval.seed_range), json_range(&interval.seeds), json_range(&interval.location), steps,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if intervals.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n]", intervals.join(",\n"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_section, run_translation_pipeline, seed_ranges, split_sections};
+
+    fn example() -> (Vec<Range<u128>>, Vec<Mapping>) {
+        let sections = split_sections(include_str!("./example.txt"));
+        let tables = sections[1..].iter().map(|s| parse_section(s)).collect();
+
+        (seed_ranges(sections[0]), tables)
+    }
+
+    #[test]
+    fn test_split_by_table() {
+        let table = parse_section("a-to-b map:\n100 0 10\n200 5 10");
+
+        assert_eq!(split_by_table(3..20, &table), vec![(3..10, Some(0)), (10..15, Some(1)), (15..20, None)]);
+    }
+
+    #[test]
+    fn test_trace_example() {
+        let (seed_ranges, tables) = example();
+        let traced = trace_seed_ranges(&seed_ranges, &tables);
+
+        assert_eq!(traced[0].location.start, 46);
+        assert!(traced[0].seeds.contains(&82));
+        assert_eq!(traced[0].seed_range, 79..93);
+
+        let total_length: u128 = traced.iter().map(|interval| interval.location.end - interval.location.start).sum();
+        assert_eq!(total_length, 14 + 13);
+
+        for interval in &traced {
+            assert_eq!(interval.steps.len(), tables.len());
+            // Later tables can split the range further, so the seeds are only part of the first input
+            assert!(interval.steps[0].input.start <= interval.seeds.start && interval.seeds.end <= interval.steps[0].input.end);
+            assert_eq!(interval.steps.last().unwrap().output, interval.location);
+
+            for (seed, location) in interval.seeds.clone().zip(interval.location.clone()) {
+                assert_eq!(run_translation_pipeline(seed, &tables), location);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trace_records_entries() {
+        let table = parse_section("seed-to-soil map:\n50 98 2\n52 50 48");
+        let seeds = 45..55;
+        let traced = trace_seed_ranges(&[seeds], std::slice::from_ref(&table));
+
+        assert_eq!(traced.len(), 2);
+        assert_eq!(traced[0].location, 45..50);
+        assert!(traced[0].steps[0].entry.is_none());
+        assert_eq!(traced[1].location, 52..57);
+        assert_eq!(traced[1].steps[0].entry.as_ref().map(|(index, _)| *index), Some(1));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let table = parse_section("seed-to-soil map:\n50 98 2\n52 50 48");
+        let seeds = 98..100;
+        let traced = trace_seed_ranges(&[seeds], &[table]);
+
+        assert_eq!(to_json(&traced), "[
+  {
+    \"seed_range\": {\"start\": 98, \"end\": 100},
+    \"seeds\": {\"start\": 98, \"end\": 100},
+    \"location\": {\"start\": 50, \"end\": 52},
+    \"steps\": [
+      {\"map\": \"seed-to-soil\", \"entry\": {\"index\": 0, \"source\": {\"start\": 98, \"end\": 100}, \"target\": {\"start\": 50, \"end\": 52}, \"length\": 2}, \"input\": {\"start\": 98, \"end\": 100}, \"output\": {\"start\": 50, \"end\": 52}}
+    ]
+  }
+]");
+        assert_eq!(to_json(&[]), "[]");
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::Mapping;
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisitState {
+    InProgress,
+    Done,
+}
+
+// The categories of an almanac and the maps between them. Categories may have any number of maps
+// going in or out of them, as long as following the maps can never lead back to where it started.
+pub struct CategoryGraph<'a> {
+    tables: &'a [Mapping],
+}
+
+impl<'a> CategoryGraph<'a> {
+    pub fn new(tables: &'a [Mapping]) -> Result<CategoryGraph<'a>, &'static str> {
+        let mut edges = HashSet::new();
+        for table in tables {
+            if !edges.insert((table.source_name.as_str(), table.target_name.as_str())) {
+                return Err("Two maps convert between the same categories");
+            }
+        }
+
+        let graph = CategoryGraph { tables };
+
+        let mut states = HashMap::new();
+        for category in graph.categories() {
+            graph.check_cycles(category, &mut states)?;
+        }
+
+        Ok(graph)
+    }
+
+    fn check_cycles(&self, category: &'a str, states: &mut HashMap<&'a str, VisitState>) -> Result<(), &'static str> {
+        match states.get(category) {
+            Some(VisitState::Done) => return Ok(()),
+            Some(VisitState::InProgress) => return Err("Category graph contains a cycle"),
+            None => {}
+        }
+
+        states.insert(category, VisitState::InProgress);
+        for table in self.tables.iter().filter(|table| table.source_name == category) {
+            self.check_cycles(&table.target_name, states)?;
+        }
+        states.insert(category, VisitState::Done);
+
+        Ok(())
+    }
+
+    // Every category mentioned by a map, sorted by name
+    pub fn categories(&self) -> Vec<&'a str> {
+        let mut categories = self.tables.iter()
+            .flat_map(|table| [table.source_name.as_str(), table.target_name.as_str()])
+            .collect::<Vec<_>>();
+
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+
+    fn check_category(&self, category: &str) -> Result<(), &'static str> {
+        if self.categories().contains(&category) {
+            Ok(())
+        } else {
+            Err("Unknown category")
+        }
+    }
+
+    // Every way of getting from source to target, in the order the maps appear in the almanac
+    pub fn paths(&self, source: &str, target: &str) -> Result<Vec<Vec<&'a Mapping>>, &'static str> {
+        self.check_category(source)?;
+        self.check_category(target)?;
+
+        let mut paths = Vec::new();
+        let mut current = Vec::new();
+        self.collect_paths(source, target, &mut current, &mut paths);
+
+        Ok(paths)
+    }
+
+    fn collect_paths(&self, category: &str, target: &str, current: &mut Vec<&'a Mapping>, paths: &mut Vec<Vec<&'a Mapping>>) {
+        if category == target {
+            paths.push(current.clone());
+            return;
+        }
+
+        for table in self.tables.iter().filter(|table| table.source_name == category) {
+            current.push(table);
+            self.collect_paths(&table.target_name, target, current, paths);
+            current.pop();
+        }
+    }
+
+    // The shortest chain of maps from source to target. When several chains are equally short,
+    // the one using the maps that come first in the almanac wins.
+    pub fn route(&self, source: &str, target: &str) -> Result<Vec<Mapping>, &'static str> {
+        self.check_category(source)?;
+        self.check_category(target)?;
+
+        let mut previous: HashMap<&str, &Mapping> = HashMap::new();
+        let mut queue = VecDeque::from([source]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == target {
+                break;
+            }
+
+            for table in self.tables.iter().filter(|table| table.source_name == category) {
+                if table.target_name != source && !previous.contains_key(table.target_name.as_str()) {
+                    previous.insert(&table.target_name, table);
+                    queue.push_back(&table.target_name);
+                }
+            }
+        }
+
+        let mut route = Vec::new();
+        let mut category = target;
+        while category != source {
+            let table = previous.get(category).ok_or("No path between the categories")?;
+            route.push((*table).clone());
+            category = &table.source_name;
+        }
+
+        route.reverse();
+        Ok(route)
+    }
+}
+
+pub fn describe_path(source: &str, path: &[&Mapping]) -> String {
+    let mut categories = vec![source];
+    categories.extend(path.iter().map(|table| table.target_name.as_str()));
+    categories.join(" -> ")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_section;
+
+    fn tables(names: &[&str]) -> Vec<Mapping> {
+        names.iter().map(|name| parse_section(&format!("{} map:\n0 0 1", name))).collect()
+    }
+
+    #[test]
+    fn test_route_through_dag() {
+        let tables = tables(&["seed-to-soil", "soil-to-water", "seed-to-light", "light-to-water", "water-to-location", "soil-to-location"]);
+        let graph = CategoryGraph::new(&tables).unwrap();
+
+        let route = graph.route("seed", "location").unwrap();
+        assert_eq!(route.iter().map(|table| table.target_name.as_str()).collect::<Vec<_>>(), vec!["soil", "location"]);
+
+        let route = graph.route("light", "location").unwrap();
+        assert_eq!(route.len(), 2);
+
+        assert!(graph.route("seed", "seed").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_all_paths() {
+        let tables = tables(&["seed-to-soil", "soil-to-water", "seed-to-light", "light-to-water", "water-to-location", "soil-to-location"]);
+        let graph = CategoryGraph::new(&tables).unwrap();
+
+        let paths = graph.paths("seed", "location").unwrap()
+            .iter()
+            .map(|path| describe_path("seed", path))
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![
+            "seed -> soil -> water -> location",
+            "seed -> soil -> location",
+            "seed -> light -> water -> location",
+        ]);
+    }
+
+    #[test]
+    fn test_route_errors() {
+        let tables = tables(&["seed-to-soil", "water-to-location"]);
+        let graph = CategoryGraph::new(&tables).unwrap();
+
+        assert_eq!(graph.route("seed", "location").err(), Some("No path between the categories"));
+        assert_eq!(graph.route("location", "seed").err(), Some("No path between the categories"));
+        assert_eq!(graph.route("seed", "humidity").err(), Some("Unknown category"));
+        assert!(graph.paths("seed", "location").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cycles_and_duplicates() {
+        assert_eq!(CategoryGraph::new(&tables(&["seed-to-soil", "soil-to-water", "water-to-seed"])).err(), Some("Category graph contains a cycle"));
+        assert_eq!(CategoryGraph::new(&tables(&["seed-to-seed"])).err(), Some("Category graph contains a cycle"));
+        assert_eq!(CategoryGraph::new(&tables(&["seed-to-soil", "seed-to-soil"])).err(), Some("Two maps convert between the same categories"));
+    }
+}
@@ -0,0 +1,168 @@
+use super::Mapping;
+use super::interval_map::IntervalMap;
+use super::provenance::json_string;
+
+// Writes a table in the same format parse_section reads, with the entries in their original order
+pub fn mapping_to_text(mapping: &Mapping) -> String {
+    let mut lines = vec![format!("{}-to-{} map:", mapping.source_name, mapping.target_name)];
+
+    lines.extend(mapping.ranges.iter().map(|entry| {
+        format!("{} {} {}", entry.target_range.start, entry.source_range.start, entry.length)
+    }));
+
+    lines.join("\n")
+}
+
+pub fn seeds_to_text(seeds: &[u128]) -> String {
+    let seeds = seeds.iter().map(|seed| seed.to_string()).collect::<Vec<_>>();
+
+    format!("seeds: {}", seeds.join(" "))
+}
+
+pub fn almanac_to_text(seeds: &[u128], tables: &[Mapping]) -> String {
+    let mut sections = vec![seeds_to_text(seeds)];
+    sections.extend(tables.iter().map(mapping_to_text));
+
+    format!("{}\n", sections.join("\n\n"))
+}
+
+fn mapping_to_json(mapping: &Mapping) -> String {
+    let entries = mapping.ranges.iter()
+        .map(|entry| format!(
+            "        {{\"destination\": {}, \"source\": {}, \"length\": {}}}",
+            entry.target_range.start, entry.source_range.start, entry.length,
+        ))
+        .collect::<Vec<_>>();
+
+    let entries = if entries.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n      ]", entries.join(",\n"))
+    };
+
+    format!(
+        "    {{\n      \"source\": {},\n      \"target\": {},\n      \"entries\": {}\n    }}",
+        json_string(&mapping.source_name), json_string(&mapping.target_name), entries,
+    )
+}
+
+// The same almanac as JSON, with the entries written out field by field
+pub fn almanac_to_json(seeds: &[u128], tables: &[Mapping]) -> String {
+    let seeds = seeds.iter().map(|seed| seed.to_string()).collect::<Vec<_>>();
+    let maps = tables.iter().map(mapping_to_json).collect::<Vec<_>>();
+
+    let maps = if maps.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n  ]", maps.join(",\n"))
+    };
+
+    format!("{{\n  \"seeds\": [{}],\n  \"maps\": {}\n}}", seeds.join(", "), maps)
+}
+
+// Rewrites a table so its entries are sorted, don't overlap, and neighbouring entries that move
+// values by the same amount are merged. Values keep mapping to the same place.
+pub fn normalize_mapping(mapping: &Mapping) -> Mapping {
+    IntervalMap::from_mapping(mapping).to_mapping(&mapping.source_name, &mapping.target_name)
+}
+
+// Merges a pipeline into a single table from its first source to its last target
+pub fn compose_pipeline(pipeline: &[Mapping]) -> Result<Mapping, &'static str> {
+    let first = pipeline.first().ok_or("Pipeline is empty")?;
+    let last = pipeline.last().ok_or("Pipeline is empty")?;
+
+    Ok(IntervalMap::from_pipeline(pipeline).to_mapping(&first.source_name, &last.target_name))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_section, parse_seeds_single, run_translation_pipeline, split_sections};
+
+    fn parse_almanac(input: &str) -> (Vec<u128>, Vec<Mapping>) {
+        let sections = split_sections(input);
+        let tables = sections[1..].iter().map(|s| parse_section(s)).collect();
+
+        (parse_seeds_single(sections[0]), tables)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = include_str!("./example.txt");
+        let (seeds, tables) = parse_almanac(input);
+
+        let text = almanac_to_text(&seeds, &tables);
+        assert_eq!(parse_almanac(&text), (seeds.clone(), tables.clone()));
+
+        // Serializing again gives exactly the same text
+        let (seeds, tables) = parse_almanac(&text);
+        assert_eq!(almanac_to_text(&seeds, &tables), text);
+    }
+
+    #[test]
+    fn test_round_trip_empty() {
+        let (seeds, tables) = parse_almanac(include_str!("./example.txt"));
+
+        let text = almanac_to_text(&[], &tables);
+        assert_eq!(parse_almanac(&text), (vec![], tables.clone()));
+
+        // A table without entries, both in the middle and at the end of the almanac
+        let empty_tables = vec![parse_section("seed-to-soil map:"), tables[1].clone(), parse_section("fertilizer-to-water map:")];
+        let text = almanac_to_text(&seeds, &empty_tables);
+        assert!(text.ends_with("fertilizer-to-water map:\n"));
+        assert_eq!(parse_almanac(&text), (seeds, empty_tables));
+    }
+
+    #[test]
+    fn test_mapping_to_text() {
+        let mapping = parse_section("seed-to-soil map:\n50 98 2\n52 50 48");
+
+        assert_eq!(mapping_to_text(&mapping), "seed-to-soil map:\n50 98 2\n52 50 48");
+        assert_eq!(seeds_to_text(&[79, 14, 55, 13]), "seeds: 79 14 55 13");
+        assert_eq!(parse_seeds_single(&seeds_to_text(&[])), vec![]);
+    }
+
+    #[test]
+    fn test_normalize_and_compose() {
+        let (_, tables) = parse_almanac(include_str!("./example.txt"));
+
+        let normalized = normalize_mapping(&parse_section("a-to-b map:\n10 0 5\n15 5 5\n7 7 3"));
+        assert_eq!(mapping_to_text(&normalized), "a-to-b map:\n10 0 10");
+
+        let composed = compose_pipeline(&tables).unwrap();
+        assert_eq!((composed.source_name.as_str(), composed.target_name.as_str()), ("seed", "location"));
+
+        // The composed table survives a round trip and still gives the same locations
+        let composed = parse_section(&mapping_to_text(&composed));
+        for seed in 0..120 {
+            assert_eq!(run_translation_pipeline(seed, &vec![composed.clone()]), run_translation_pipeline(seed, &tables));
+        }
+
+        assert!(compose_pipeline(&[]).is_err());
+    }
+
+    #[test]
+    fn test_almanac_to_json() {
+        let tables = vec![parse_section("seed-to-soil map:\n50 98 2"), parse_section("soil-to-water map:")];
+
+        assert_eq!(almanac_to_json(&[79, 14], &tables), "{
+  \"seeds\": [79, 14],
+  \"maps\": [
+    {
+      \"source\": \"seed\",
+      \"target\": \"soil\",
+      \"entries\": [
+        {\"destination\": 50, \"source\": 98, \"length\": 2}
+      ]
+    },
+    {
+      \"source\": \"soil\",
+      \"target\": \"water\",
+      \"entries\": []
+    }
+  ]
+}");
+        assert_eq!(almanac_to_json(&[], &[]), "{\n  \"seeds\": [],\n  \"maps\": []\n}");
+    }
+}
@@ -0,0 +1,155 @@
+use super::Race;
+use super::boat::{find_peak, BoatModel};
+use super::solver::WinningInterval;
+
+#[derive(Debug, PartialEq)]
+pub struct RaceAnalysis {
+    pub time: u64,
+    pub record: u64,
+    pub optimal_hold_time: u64,
+    pub max_distance: u128,
+    pub winning_interval: Option<WinningInterval>,
+    // How far the best possible run beats the record by, negative when the record can't be beaten
+    pub margin: i128,
+}
+
+impl RaceAnalysis {
+    // The margin as a percentage of the record. A small number means a slightly better boat would
+    // make the record fall for good, while a large one means the record was set by a much weaker run.
+    pub fn margin_percent(&self) -> f64 {
+        if self.record == 0 {
+            return f64::INFINITY;
+        }
+
+        self.margin as f64 * 100.0 / self.record as f64
+    }
+}
+
+pub fn analyze_race(model: &dyn BoatModel, race: &Race) -> RaceAnalysis {
+    let optimal_hold_time = find_peak(model, race.time);
+    let max_distance = model.distance(optimal_hold_time, race.time);
+
+    RaceAnalysis {
+        time: race.time,
+        record: race.max_distance,
+        optimal_hold_time,
+        max_distance,
+        winning_interval: model.winning_interval(race),
+        margin: max_distance as i128 - race.max_distance as i128,
+    }
+}
+
+pub fn report(analyses: &[RaceAnalysis]) -> String {
+    let mut lines = vec![format!(
+        "{:>5} {:>16} {:>16} {:>12} {:>18} {:>27} {:>16} {:>10}",
+        "race", "time", "record", "best hold", "best distance", "winning hold times", "margin", "margin %",
+    )];
+
+    for (index, analysis) in analyses.iter().enumerate() {
+        let interval = match analysis.winning_interval {
+            Some(interval) => format!("{}..={} ({})", interval.first, interval.last, interval.count()),
+            None => "none".to_string(),
+        };
+
+        lines.push(format!(
+            "{:>5} {:>16} {:>16} {:>12} {:>18} {:>27} {:>16} {:>10.2}",
+            index + 1, analysis.time, analysis.record, analysis.optimal_hold_time, analysis.max_distance, interval, analysis.margin, analysis.margin_percent(),
+        ));
+    }
+
+    lines.join("\n")
+}
+
+// Up to samples evenly spaced hold times from 0 to the race time, both ends included
+fn sample_hold_times(race_time: u64, samples: u64) -> Vec<u64> {
+    if race_time < samples {
+        return (0..=race_time).collect();
+    }
+
+    if samples < 2 {
+        return vec![0; samples as usize];
+    }
+
+    (0..samples).map(|i| (i as u128 * race_time as u128 / (samples - 1) as u128) as u64).collect()
+}
+
+// The distance curve of every race as CSV, one row per sampled hold time
+pub fn curves_csv(model: &dyn BoatModel, races: &[Race], samples: u64) -> String {
+    let mut lines = vec!["race,hold_time,distance,record,beats_record".to_string()];
+
+    for (index, race) in races.iter().enumerate() {
+        for hold_time in sample_hold_times(race.time, samples) {
+            let distance = model.distance(hold_time, race.time);
+
+            lines.push(format!("{},{},{},{},{}", index + 1, hold_time, distance, race.max_distance, distance > race.max_distance as u128));
+        }
+    }
+
+    format!("{}\n", lines.join("\n"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::boat::{CappedSpeed, LinearCharge};
+
+    #[test]
+    fn test_analyze_race() {
+        let analysis = analyze_race(&LinearCharge, &Race { time: 30, max_distance: 200 });
+
+        assert_eq!(analysis, RaceAnalysis {
+            time: 30,
+            record: 200,
+            optimal_hold_time: 15,
+            max_distance: 225,
+            winning_interval: Some(WinningInterval { first: 11, last: 19 }),
+            margin: 25,
+        });
+        assert_eq!(analysis.margin_percent(), 12.5);
+    }
+
+    #[test]
+    fn test_analyze_unbeatable_race() {
+        let analysis = analyze_race(&CappedSpeed { rate: 2, max_speed: 7 }, &Race { time: 30, max_distance: 200 });
+
+        assert_eq!(analysis.optimal_hold_time, 4);
+        assert_eq!(analysis.max_distance, 7 * 26);
+        assert_eq!(analysis.margin, 182 - 200);
+        assert_eq!(analysis.winning_interval, None);
+    }
+
+    #[test]
+    fn test_report() {
+        let analyses = vec![analyze_race(&LinearCharge, &Race { time: 7, max_distance: 9 }), analyze_race(&LinearCharge, &Race { time: 3, max_distance: 5 })];
+        let report = report(&analyses);
+        let lines = report.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("2..=5 (4)"));
+        assert!(lines[1].ends_with("33.33"));
+        assert!(lines[2].contains("none"));
+        assert!(lines[2].ends_with("-60.00"));
+    }
+
+    #[test]
+    fn test_sample_hold_times() {
+        assert_eq!(sample_hold_times(4, 10), vec![0, 1, 2, 3, 4]);
+        assert_eq!(sample_hold_times(100, 5), vec![0, 25, 50, 75, 100]);
+        assert_eq!(sample_hold_times(u64::MAX, 2), vec![0, u64::MAX]);
+        assert!(sample_hold_times(100, 0).is_empty());
+    }
+
+    #[test]
+    fn test_curves_csv() {
+        let csv = curves_csv(&LinearCharge, &[Race { time: 4, max_distance: 3 }], 10);
+
+        assert_eq!(csv, "race,hold_time,distance,record,beats_record
+1,0,0,3,false
+1,1,3,3,false
+1,2,4,3,true
+1,3,3,3,false
+1,4,0,3,false
+");
+    }
+}
@@ -0,0 +1,294 @@
+use super::Race;
+use super::solver::{solve_race, WinningInterval};
+
+// How far a boat gets when the button is held for hold_time ms of a race lasting race_time ms.
+// Every model has to rise to a single peak and fall off after it, which the search relies on.
+pub trait BoatModel {
+    fn name(&self) -> String;
+
+    fn distance(&self, hold_time: u64, race_time: u64) -> u128;
+
+    // Models with a closed form override this, everything else falls back to searching
+    fn winning_interval(&self, race: &Race) -> Option<WinningInterval> {
+        search_winning_interval(self, race)
+    }
+}
+
+// The original boat: 1 mm/ms of speed for every ms the button is held
+pub struct LinearCharge;
+
+impl BoatModel for LinearCharge {
+    fn name(&self) -> String {
+        "linear".to_string()
+    }
+
+    fn distance(&self, hold_time: u64, race_time: u64) -> u128 {
+        if hold_time >= race_time {
+            return 0;
+        }
+
+        hold_time as u128 * (race_time - hold_time) as u128
+    }
+
+    fn winning_interval(&self, race: &Race) -> Option<WinningInterval> {
+        solve_race(race)
+    }
+}
+
+// Like the linear boat, but gaining rate mm/ms of speed for every ms held
+pub struct ChargeRate {
+    pub rate: u64,
+}
+
+impl BoatModel for ChargeRate {
+    fn name(&self) -> String {
+        format!("rate:{}", self.rate)
+    }
+
+    // The rate comes straight from the command line, so the distance saturates rather than overflowing
+    fn distance(&self, hold_time: u64, race_time: u64) -> u128 {
+        (self.rate as u128).saturating_mul(LinearCharge.distance(hold_time, race_time))
+    }
+
+    // rate * d > record exactly when d > record / rate rounded down, so this is the linear race
+    // against a scaled down record
+    fn winning_interval(&self, race: &Race) -> Option<WinningInterval> {
+        if self.rate == 0 {
+            return None;
+        }
+
+        solve_race(&Race { time: race.time, max_distance: race.max_distance / self.rate })
+    }
+}
+
+// Charges at rate mm/ms per ms held, but never goes faster than max_speed
+pub struct CappedSpeed {
+    pub rate: u64,
+    pub max_speed: u64,
+}
+
+impl BoatModel for CappedSpeed {
+    fn name(&self) -> String {
+        format!("capped:{}:{}", self.rate, self.max_speed)
+    }
+
+    fn distance(&self, hold_time: u64, race_time: u64) -> u128 {
+        if hold_time >= race_time {
+            return 0;
+        }
+
+        let speed = (self.rate as u128 * hold_time as u128).min(self.max_speed as u128);
+        speed.saturating_mul((race_time - hold_time) as u128)
+    }
+}
+
+// Charges at rate mm/ms per ms held, then loses deceleration mm/ms of speed after every ms on the water
+pub struct Drag {
+    pub rate: u64,
+    pub deceleration: u64,
+}
+
+impl BoatModel for Drag {
+    fn name(&self) -> String {
+        format!("drag:{}:{}", self.rate, self.deceleration)
+    }
+
+    fn distance(&self, hold_time: u64, race_time: u64) -> u128 {
+        if hold_time >= race_time {
+            return 0;
+        }
+
+        let speed = self.rate as u128 * hold_time as u128;
+        let deceleration = self.deceleration as u128;
+        let remaining_time = (race_time - hold_time) as u128;
+
+        // Only the ms where the boat still has some speed left count
+        let moving_time = if deceleration == 0 {
+            remaining_time
+        } else {
+            remaining_time.min(speed.div_ceil(deceleration))
+        };
+
+        if moving_time == 0 {
+            return 0;
+        }
+
+        // The speeds form an arithmetic series: speed, speed - deceleration, ... down to the last speed
+        // before stopping. The distance is the number of terms times their average, halving whichever
+        // of the two is even so it stays exact, and saturating where it doesn't fit in a u128.
+        let last_speed = speed - deceleration * (moving_time - 1);
+
+        if moving_time % 2 == 0 {
+            (moving_time / 2).saturating_mul(speed.saturating_add(last_speed))
+        } else {
+            // An odd number of terms means speed and last_speed are both odd or both even
+            moving_time.saturating_mul(speed / 2 + last_speed / 2 + (speed & last_speed & 1))
+        }
+    }
+}
+
+// Finds the hold time with the furthest distance by ternary search. Ties between neighbours are fine,
+// as long as the distance never goes flat anywhere but at the peak.
+pub fn find_peak<M: BoatModel + ?Sized>(model: &M, race_time: u64) -> u64 {
+    let (mut low, mut high) = (0, race_time);
+
+    while high - low > 2 {
+        let third = (high - low) / 3;
+        let (left, right) = (low + third, high - third);
+
+        if model.distance(left, race_time) < model.distance(right, race_time) {
+            low = left + 1;
+        } else {
+            high = right;
+        }
+    }
+
+    (low..=high).max_by_key(|hold_time| model.distance(*hold_time, race_time)).unwrap()
+}
+
+// Smallest hold time in low..=high where wins is true, given that wins only ever flips from false to true
+fn first_win(mut low: u64, mut high: u64, wins: impl Fn(u64) -> bool) -> u64 {
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        if wins(middle) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+
+    low
+}
+
+// Numeric root finding for models without a closed form: find the peak, then binary search for where
+// the distance crosses the record on either side of it
+pub fn search_winning_interval<M: BoatModel + ?Sized>(model: &M, race: &Race) -> Option<WinningInterval> {
+    let record = race.max_distance as u128;
+    let beats = |hold_time: u64| model.distance(hold_time, race.time) > record;
+
+    let peak = find_peak(model, race.time);
+    if !beats(peak) {
+        return None;
+    }
+
+    let first = first_win(0, peak, beats);
+    let last = first_win(peak, race.time, |hold_time| !beats(hold_time)) - 1;
+
+    Some(WinningInterval { first, last })
+}
+
+pub fn count_winning_hold_times<M: BoatModel + ?Sized>(model: &M, race: &Race) -> u64 {
+    model.winning_interval(race).map_or(0, |interval| interval.count())
+}
+
+pub fn model_from_name(name: &str) -> Result<Box<dyn BoatModel>, &'static str> {
+    let parts = name.split(':').collect::<Vec<_>>();
+    let number = |part: &str| part.parse::<u64>().map_err(|_| "Invalid boat model parameter");
+
+    match parts.as_slice() {
+        ["linear"] => Ok(Box::new(LinearCharge)),
+        ["rate", rate] => Ok(Box::new(ChargeRate { rate: number(rate)? })),
+        ["capped", rate, max_speed] => Ok(Box::new(CappedSpeed { rate: number(rate)?, max_speed: number(max_speed)? })),
+        ["drag", rate, deceleration] => Ok(Box::new(Drag { rate: number(rate)?, deceleration: number(deceleration)? })),
+        _ => Err("Unknown boat model"),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::get_boat_distance_travelled_with_hold_time;
+
+    fn brute_force(model: &dyn BoatModel, race: &Race) -> Option<WinningInterval> {
+        let winners = (0..=race.time)
+            .filter(|hold_time| model.distance(*hold_time, race.time) > race.max_distance as u128)
+            .collect::<Vec<_>>();
+
+        Some(WinningInterval { first: *winners.first()?, last: *winners.last()? })
+    }
+
+    fn models() -> Vec<Box<dyn BoatModel>> {
+        vec![
+            Box::new(LinearCharge),
+            Box::new(ChargeRate { rate: 3 }),
+            Box::new(CappedSpeed { rate: 2, max_speed: 9 }),
+            Box::new(CappedSpeed { rate: 1, max_speed: 1000 }),
+            Box::new(Drag { rate: 2, deceleration: 1 }),
+            Box::new(Drag { rate: 1, deceleration: 3 }),
+            Box::new(Drag { rate: 5, deceleration: 0 }),
+        ]
+    }
+
+    #[test]
+    fn test_linear_matches_original_distance() {
+        for hold_time in 0..=8 {
+            assert_eq!(LinearCharge.distance(hold_time, 7), get_boat_distance_travelled_with_hold_time(hold_time, 7) as u128);
+        }
+    }
+
+    #[test]
+    fn test_drag_distance() {
+        let drag = Drag { rate: 2, deceleration: 3 };
+
+        // 8 mm/ms, then 5, 2 and stopped
+        assert_eq!(drag.distance(4, 10), 8 + 5 + 2);
+        assert_eq!(drag.distance(4, 6), 8 + 5);
+        assert_eq!(drag.distance(0, 10), 0);
+    }
+
+    #[test]
+    fn test_models_match_brute_force() {
+        for model in models() {
+            for time in 0..40 {
+                for max_distance in (0..200).step_by(7) {
+                    let race = Race { time, max_distance };
+
+                    assert_eq!(model.winning_interval(&race), brute_force(model.as_ref(), &race), "{} {} {}", model.name(), time, max_distance);
+                    assert_eq!(search_winning_interval(model.as_ref(), &race), brute_force(model.as_ref(), &race), "{} {} {}", model.name(), time, max_distance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_large_race() {
+        let race = Race { time: 71530, max_distance: 940200 };
+
+        assert_eq!(search_winning_interval(&LinearCharge, &race), LinearCharge.winning_interval(&race));
+        assert_eq!(count_winning_hold_times(&LinearCharge, &race), 71503);
+    }
+
+    #[test]
+    fn test_huge_parameters() {
+        let race = Race { time: 1 << 40, max_distance: u64::MAX - 1 };
+        let winners = Some(WinningInterval { first: 1, last: race.time - 1 });
+
+        let rate = model_from_name("rate:18446744073709551615").unwrap();
+        assert_eq!(rate.distance(1 << 39, race.time), u128::MAX);
+        assert_eq!(rate.winning_interval(&race), winners);
+        assert_eq!(search_winning_interval(rate.as_ref(), &race), winners);
+
+        let models: Vec<Box<dyn BoatModel>> = vec![
+            Box::new(CappedSpeed { rate: u64::MAX, max_speed: u64::MAX }),
+            Box::new(Drag { rate: u64::MAX, deceleration: 1 }),
+            Box::new(Drag { rate: u64::MAX, deceleration: 0 }),
+        ];
+        for model in models {
+            assert_eq!(search_winning_interval(model.as_ref(), &race), winners, "{}", model.name());
+        }
+        assert_eq!(Drag { rate: u64::MAX, deceleration: 0 }.distance(1 << 39, race.time), u128::MAX);
+    }
+
+    #[test]
+    fn test_model_from_name() {
+        for model in models() {
+            assert_eq!(model_from_name(&model.name()).unwrap().name(), model.name());
+        }
+
+        assert!(model_from_name("rate").is_err());
+        assert!(model_from_name("capped:1:x").is_err());
+        assert!(model_from_name("warp").is_err());
+    }
+}
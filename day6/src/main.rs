@@ -1,10 +1,20 @@
 use regex::Regex;
 
+mod analysis;
+mod boat;
+mod planner;
+mod solver;
+
+use std::convert::TryFrom;
+
+use boat::BoatModel;
+
 struct Race {
     time: u64,
     max_distance: u64,
 }
 
+#[cfg(test)]
 fn get_boat_distance_travelled_with_hold_time(hold_time: u64, total_time: u64) -> u64 {
     if hold_time >= total_time {
         return 0;
@@ -21,13 +31,15 @@ fn get_boat_distance_travelled_with_hold_time(hold_time: u64, total_time: u64) -
     distance_travelled
 }
 
+// Steps through every hold time, which is far too slow for part 2. Kept to check the solver against.
+#[cfg(test)]
 fn simulate_race_winning_conditions(race: &Race) -> Vec<u64> {
     let mut valid_button_hold_times: Vec<u64> = Vec::new();
 
     let mut current_button_hold_time = 1;
 
-    // First find the max button hold time that will allow the boat to beat the record
-    while get_boat_distance_travelled_with_hold_time(current_button_hold_time, race.time) < race.max_distance {
+    // First find the min button hold time that will allow the boat to beat the record
+    while current_button_hold_time < race.time && get_boat_distance_travelled_with_hold_time(current_button_hold_time, race.time) <= race.max_distance {
         current_button_hold_time += 1;
     }
 
@@ -74,31 +86,86 @@ fn parse_input_part2(input: &str) -> Race {
     }
 }
 
-fn simulate_races(races: &Vec<Race>) -> usize {
+fn simulate_races(races: &Vec<Race>, model: &dyn BoatModel) -> u64 {
     let total_lengths: Vec<_> = races.iter()
-        .map(|race| {
-            let valid_hold_times = simulate_race_winning_conditions(race);
-            valid_hold_times.len()
-        })
+        .map(|race| boat::count_winning_hold_times(model, race))
         .collect();
 
     total_lengths.iter().product()
 }
 
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("Usage: day6 [--model <linear|rate:<rate>|capped:<rate>:<max speed>|drag:<rate>:<deceleration>>] [--plan <budget> <won|margin> | --report | --curve <samples>]");
+    std::process::exit(1);
+}
+
 fn main() {
     let input = include_str!("./input.txt");
 
+    let mut model: Box<dyn BoatModel> = Box::new(boat::LinearCharge);
+    let mut plan = None;
+    let mut show_report = false;
+    let mut curve_samples = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| exit_with_error("Missing argument value"));
+
+        match arg.as_str() {
+            "--model" => {
+                model = boat::model_from_name(&value()).unwrap_or_else(|error| exit_with_error(error));
+                println!("Boat model: {}", model.name());
+            }
+            "--plan" => {
+                let budget = value().parse::<u64>().unwrap_or_else(|_| exit_with_error("Invalid budget"));
+                let objective = planner::Objective::try_from(value().as_str()).unwrap_or_else(|error| exit_with_error(error));
+                plan = Some((budget, objective));
+            }
+            "--report" => show_report = true,
+            "--curve" => curve_samples = Some(value().parse::<u64>().unwrap_or_else(|_| exit_with_error("Invalid number of samples"))),
+            _ => exit_with_error("Unknown argument"),
+        }
+    }
+
     let part1_races = parse_input_part1(input);
-    let part1_sum = simulate_races(&part1_races);
+
+    // day6 --plan <budget> <won|margin> picks hold times for the part 1 races when they share one budget
+    if let Some((budget, objective)) = plan {
+        let plan = planner::plan_races(model.as_ref(), &part1_races, budget, objective).unwrap_or_else(|error| exit_with_error(error));
+
+        for (index, hold_time) in plan.hold_times.iter().enumerate() {
+            println!("Race {}: hold for {} ms", index + 1, hold_time);
+        }
+        println!("{} races won, {} mm total margin, {}/{} ms of budget used", plan.races_won, plan.total_margin, plan.budget_used, budget);
+        return;
+    }
+
+    // day6 --curve <samples> prints the distance for that many hold times of every part 1 race as CSV
+    if let Some(samples) = curve_samples {
+        print!("{}", analysis::curves_csv(model.as_ref(), &part1_races, samples));
+        return;
+    }
+
+    let part1_sum = simulate_races(&part1_races, model.as_ref());
 
     println!("Part 1: {}", part1_sum);
 
     let part2_race = parse_input_part2(input);
-    let part2_lengths = simulate_race_winning_conditions(&part2_race);
-
-    let part2_sum: u64 = part2_lengths.len() as u64;
+    let part2_sum = boat::count_winning_hold_times(model.as_ref(), &part2_race);
 
     println!("Part 2: {}", part2_sum);
+
+    // day6 --report adds a breakdown of how far each record can be beaten, the part 2 race last
+    if show_report {
+        let analyses = part1_races.iter()
+            .chain(std::iter::once(&part2_race))
+            .map(|race| analysis::analyze_race(model.as_ref(), race))
+            .collect::<Vec<_>>();
+
+        println!();
+        println!("{}", analysis::report(&analyses));
+    }
 }
 
 
@@ -129,4 +196,24 @@ mod tests {
         let valid_button_hold_times = simulate_race_winning_conditions(&race);
         assert_eq!(valid_button_hold_times, vec![2, 3, 4, 5]);
     }
+
+    #[test]
+    fn test_simulate_race_winning_conditions_tied_record() {
+        // Holding for 10 ms only ties the record
+        let race = Race {
+            time: 30,
+            max_distance: 200,
+        };
+
+        let valid_button_hold_times = simulate_race_winning_conditions(&race);
+        assert_eq!(valid_button_hold_times, (11..=19).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_example() {
+        let input = include_str!("./example.txt");
+
+        assert_eq!(simulate_races(&parse_input_part1(input), &boat::LinearCharge), 288);
+        assert_eq!(boat::count_winning_hold_times(&boat::LinearCharge, &parse_input_part2(input)), 71503);
+    }
 }
\ No newline at end of file
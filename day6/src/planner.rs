@@ -0,0 +1,241 @@
+use std::convert::TryFrom;
+
+use super::Race;
+use super::boat::BoatModel;
+
+// The margin objective tries every hold time for every unit of budget, so give up past this many steps
+const MAX_SEARCH_STEPS: u128 = 100_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    RacesWon,
+    TotalMargin,
+}
+
+impl TryFrom<&str> for Objective {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "won" => Ok(Objective::RacesWon),
+            "margin" => Ok(Objective::TotalMargin),
+            _ => Err("Unknown objective, expected won or margin"),
+        }
+    }
+}
+
+// A hold time for every race. Races that aren't worth spending budget on get a hold time of 0.
+#[derive(Debug, PartialEq)]
+pub struct Plan {
+    pub hold_times: Vec<u64>,
+    pub races_won: usize,
+    pub total_margin: u128,
+    pub budget_used: u64,
+}
+
+// How far past the record the boat ends up, or None if it doesn't beat it
+fn margin(model: &dyn BoatModel, race: &Race, hold_time: u64) -> Option<u128> {
+    model.distance(hold_time, race.time).checked_sub(race.max_distance as u128).filter(|margin| *margin > 0)
+}
+
+fn build_plan(model: &dyn BoatModel, races: &[Race], hold_times: Vec<u64>) -> Plan {
+    let margins = races.iter().zip(hold_times.iter()).filter_map(|(race, hold_time)| margin(model, race, *hold_time)).collect::<Vec<_>>();
+
+    Plan {
+        races_won: margins.len(),
+        total_margin: margins.iter().sum(),
+        budget_used: hold_times.iter().sum(),
+        hold_times,
+    }
+}
+
+// Every race costs its shortest winning hold time, so winning as many as possible means buying the cheapest first
+fn plan_races_won(model: &dyn BoatModel, races: &[Race], budget: u64) -> Plan {
+    let mut costs = races.iter()
+        .enumerate()
+        .filter_map(|(index, race)| model.winning_interval(race).map(|interval| (interval.first, index)))
+        .collect::<Vec<_>>();
+    costs.sort();
+
+    let mut hold_times = vec![0; races.len()];
+    let mut remaining = budget;
+
+    for (cost, index) in costs {
+        if cost > remaining {
+            break;
+        }
+
+        hold_times[index] = cost;
+        remaining -= cost;
+    }
+
+    build_plan(model, races, hold_times)
+}
+
+// A knapsack over the budget: best[b] is the largest total margin of the races so far using at most b ms
+// of holding. Only winning hold times are worth considering, anything else scores the same as not
+// holding at all.
+fn plan_total_margin(model: &dyn BoatModel, races: &[Race], budget: u64) -> Result<Plan, &'static str> {
+    let intervals = races.iter()
+        .map(|race| model.winning_interval(race).filter(|interval| interval.first <= budget))
+        .collect::<Vec<_>>();
+
+    // There's no point in a budget larger than what holding for the longest winning time everywhere costs
+    let budget = budget.min(intervals.iter().flatten().map(|interval| interval.last).sum());
+
+    let steps = intervals.iter()
+        .map(|interval| interval.map_or(0, |interval| interval.last.min(budget) - interval.first + 1) as u128 + 1)
+        .sum::<u128>() * (budget as u128 + 1);
+    if steps > MAX_SEARCH_STEPS {
+        return Err("Budget is too large to search every hold time");
+    }
+
+    let options = races.iter()
+        .zip(intervals.iter())
+        .map(|(race, interval)| match interval {
+            Some(interval) => (interval.first..=interval.last.min(budget))
+                .filter_map(|hold_time| margin(model, race, hold_time).map(|margin| (hold_time, margin)))
+                .collect::<Vec<_>>(),
+            None => Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let budget = budget as usize;
+    let mut best = vec![0u128; budget + 1];
+    let mut choices = Vec::with_capacity(races.len());
+
+    for options in &options {
+        let mut next = best.clone();
+        let mut choice = vec![0u64; budget + 1];
+
+        for spent in 0..=budget {
+            for (hold_time, margin) in options {
+                let hold_time_index = *hold_time as usize;
+                if hold_time_index > spent {
+                    break;
+                }
+
+                let candidate = best[spent - hold_time_index] + margin;
+                if candidate > next[spent] {
+                    next[spent] = candidate;
+                    choice[spent] = *hold_time;
+                }
+            }
+        }
+
+        best = next;
+        choices.push(choice);
+    }
+
+    // Walk back through the choices to find the hold time picked for each race
+    let mut hold_times = vec![0; races.len()];
+    let mut spent = budget;
+    for (index, choice) in choices.iter().enumerate().rev() {
+        hold_times[index] = choice[spent];
+        spent -= choice[spent] as usize;
+    }
+
+    Ok(build_plan(model, races, hold_times))
+}
+
+// Picks a hold time for every race when all of them together may only be held for budget ms
+pub fn plan_races(model: &dyn BoatModel, races: &[Race], budget: u64, objective: Objective) -> Result<Plan, &'static str> {
+    match objective {
+        Objective::RacesWon => Ok(plan_races_won(model, races, budget)),
+        Objective::TotalMargin => plan_total_margin(model, races, budget),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::boat::{CappedSpeed, LinearCharge};
+
+    fn example_races() -> Vec<Race> {
+        vec![
+            Race { time: 7, max_distance: 9 },
+            Race { time: 15, max_distance: 40 },
+            Race { time: 30, max_distance: 200 },
+        ]
+    }
+
+    // Tries every combination of hold times that fits in the budget, giving the most races won and the
+    // largest total margin
+    fn exhaustive(model: &dyn BoatModel, races: &[Race], budget: u64) -> (usize, u128) {
+        let Some((race, rest)) = races.split_first() else {
+            return (0, 0);
+        };
+
+        let mut best = (0, 0);
+        for hold_time in 0..=race.time.min(budget) {
+            let margin = margin(model, race, hold_time);
+            let (won, total) = exhaustive(model, rest, budget - hold_time);
+
+            best.0 = best.0.max(won + margin.is_some() as usize);
+            best.1 = best.1.max(total + margin.unwrap_or(0));
+        }
+
+        best
+    }
+
+    #[test]
+    fn test_plan_races_won() {
+        let plan = plan_races(&LinearCharge, &example_races(), 6, Objective::RacesWon).unwrap();
+
+        assert_eq!(plan, Plan { hold_times: vec![2, 4, 0], races_won: 2, total_margin: 5, budget_used: 6 });
+    }
+
+    #[test]
+    fn test_plan_total_margin() {
+        let races = example_races();
+
+        let plan = plan_races(&LinearCharge, &races, 6, Objective::TotalMargin).unwrap();
+        assert_eq!(plan, Plan { hold_times: vec![0, 6, 0], races_won: 1, total_margin: 14, budget_used: 6 });
+
+        let plan = plan_races(&LinearCharge, &races, 10, Objective::TotalMargin).unwrap();
+        assert_eq!(plan, Plan { hold_times: vec![3, 7, 0], races_won: 2, total_margin: 19, budget_used: 10 });
+
+        let plan = plan_races(&LinearCharge, &races, 1, Objective::TotalMargin).unwrap();
+        assert_eq!(plan, Plan { hold_times: vec![0, 0, 0], races_won: 0, total_margin: 0, budget_used: 0 });
+    }
+
+    #[test]
+    fn test_plans_match_exhaustive_search() {
+        let races = vec![
+            Race { time: 7, max_distance: 9 },
+            Race { time: 9, max_distance: 12 },
+            Race { time: 12, max_distance: 30 },
+        ];
+        let capped = CappedSpeed { rate: 2, max_speed: 7 };
+
+        for model in [&LinearCharge as &dyn BoatModel, &capped] {
+            for budget in 0..25 {
+                let (won, margin) = exhaustive(model, &races, budget);
+
+                let plan = plan_races(model, &races, budget, Objective::RacesWon).unwrap();
+                assert_eq!(plan.races_won, won, "{} {}", model.name(), budget);
+                assert!(plan.budget_used <= budget);
+
+                let plan = plan_races(model, &races, budget, Objective::TotalMargin).unwrap();
+                assert_eq!(plan.total_margin, margin, "{} {}", model.name(), budget);
+                assert!(plan.budget_used <= budget);
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_too_large() {
+        let races = vec![Race { time: 71530, max_distance: 940200 }, Race { time: 71530, max_distance: 940200 }];
+
+        assert!(plan_races(&LinearCharge, &races, 100_000, Objective::TotalMargin).is_err());
+        assert_eq!(plan_races(&LinearCharge, &races, 100_000, Objective::RacesWon).unwrap().races_won, 2);
+    }
+
+    #[test]
+    fn test_objective_from_str() {
+        assert_eq!(Objective::try_from("won"), Ok(Objective::RacesWon));
+        assert_eq!(Objective::try_from("margin"), Ok(Objective::TotalMargin));
+        assert!(Objective::try_from("fastest").is_err());
+    }
+}
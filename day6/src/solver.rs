@@ -0,0 +1,123 @@
+use super::Race;
+
+// The hold times that beat the record, first and last included
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WinningInterval {
+    pub first: u64,
+    pub last: u64,
+}
+
+impl WinningInterval {
+    pub fn count(&self) -> u64 {
+        self.last - self.first + 1
+    }
+}
+
+// Largest r with r * r <= n, found with Newton's method so it stays exact for any u128
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Start from (n + 1) / 2, written so it can't overflow
+    let mut x = n;
+    let mut y = n / 2 + n % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+
+    x
+}
+
+// Holding for h ms travels h * (time - h) mm, so the record is beaten when h^2 - time * h + distance < 0.
+// The roots are (time ± sqrt(time^2 - 4 * distance)) / 2. The integer square root can be off by one
+// from the real root, so the bound is nudged until it sits exactly on the first winning hold time.
+pub fn solve_race(race: &Race) -> Option<WinningInterval> {
+    let time = race.time as u128;
+    let distance = race.max_distance as u128;
+
+    let wins = |hold: u128| hold * (time - hold) > distance;
+
+    // The distance peaks at half the race time, so if that doesn't win nothing does
+    let peak = time / 2;
+    if !wins(peak) {
+        return None;
+    }
+
+    let discriminant = time * time - 4 * distance;
+    let mut first = (time - isqrt(discriminant)) / 2;
+
+    while !wins(first) {
+        first += 1;
+    }
+    while first > 0 && wins(first - 1) {
+        first -= 1;
+    }
+
+    // The curve is symmetric, so the last winning hold time mirrors the first
+    Some(WinningInterval {
+        first: first as u64,
+        last: (time - first) as u64,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::simulate_race_winning_conditions;
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10_000u128 {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n, "isqrt({})", n);
+        }
+
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(isqrt((u64::MAX as u128) * (u64::MAX as u128) - 1), u64::MAX as u128 - 1);
+    }
+
+    #[test]
+    fn test_solve_example() {
+        assert_eq!(solve_race(&Race { time: 7, max_distance: 9 }), Some(WinningInterval { first: 2, last: 5 }));
+        assert_eq!(solve_race(&Race { time: 15, max_distance: 40 }), Some(WinningInterval { first: 4, last: 11 }));
+        assert_eq!(solve_race(&Race { time: 30, max_distance: 200 }), Some(WinningInterval { first: 11, last: 19 }));
+        assert_eq!(solve_race(&Race { time: 71530, max_distance: 940200 }).map(|interval| interval.count()), Some(71503));
+    }
+
+    #[test]
+    fn test_solve_matches_simulation() {
+        for time in 0..60 {
+            for max_distance in 0..(time * time / 4 + 3) {
+                let race = Race { time, max_distance };
+                let expected = simulate_race_winning_conditions(&race);
+
+                match solve_race(&race) {
+                    Some(interval) => assert_eq!((interval.first..=interval.last).collect::<Vec<_>>(), expected, "{} {}", time, max_distance),
+                    None => assert!(expected.is_empty(), "{} {}", time, max_distance),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_without_winners() {
+        // The best hold time only ties the record
+        assert_eq!(solve_race(&Race { time: 10, max_distance: 25 }), None);
+        assert_eq!(solve_race(&Race { time: 0, max_distance: 0 }), None);
+        assert_eq!(solve_race(&Race { time: 3, max_distance: 100 }), None);
+    }
+
+    #[test]
+    fn test_solve_huge_race() {
+        // Anything goes except holding for no time at all or for the whole race
+        let race = Race { time: u64::MAX, max_distance: 0 };
+        assert_eq!(solve_race(&race), Some(WinningInterval { first: 1, last: u64::MAX - 1 }));
+
+        // Holding for 1 ms only gets u64::MAX - 1 mm, 2 ms is the first hold time to beat the record
+        let race = Race { time: u64::MAX, max_distance: u64::MAX };
+        assert_eq!(solve_race(&race), Some(WinningInterval { first: 2, last: u64::MAX - 2 }));
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use super::{CamelCard, CamelCardsHand};
+
+// Strongest first, so sorting hand types puts the best hands on top just like sorting hands does
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandType {
+    FiveOfAKind,
+    FourOfAKind,
+    FullHouse,
+    ThreeOfAKind,
+    TwoPair,
+    OnePair,
+    HighCard,
+}
+
+impl HandType {
+    pub const ALL: [HandType; 7] = [
+        HandType::FiveOfAKind,
+        HandType::FourOfAKind,
+        HandType::FullHouse,
+        HandType::ThreeOfAKind,
+        HandType::TwoPair,
+        HandType::OnePair,
+        HandType::HighCard,
+    ];
+
+    // Only the two largest counts of the sorted signature matter, e.g. [3, 2] is a full house.
+    // Hands larger than five cards can hold even more of a kind, those still count as five of a kind.
+    pub fn from_counts(counts: impl Iterator<Item = u32>) -> HandType {
+        let mut signature = counts.collect::<Vec<_>>();
+        signature.sort_by(|a, b| b.cmp(a));
+
+        let first = signature.first().copied().unwrap_or(0);
+        let second = signature.get(1).copied().unwrap_or(0);
+
+        match (first, second) {
+            (5.., _) => HandType::FiveOfAKind,
+            (4, _) => HandType::FourOfAKind,
+            (3, 2..) => HandType::FullHouse,
+            (3, _) => HandType::ThreeOfAKind,
+            (2, 2) => HandType::TwoPair,
+            (2, _) => HandType::OnePair,
+            _ => HandType::HighCard,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HandType::FiveOfAKind => "five of a kind",
+            HandType::FourOfAKind => "four of a kind",
+            HandType::FullHouse => "full house",
+            HandType::ThreeOfAKind => "three of a kind",
+            HandType::TwoPair => "two pair",
+            HandType::OnePair => "one pair",
+            HandType::HighCard => "high card",
+        }
+    }
+}
+
+impl fmt::Display for HandType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// How the wildcards of a hand were spent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WildcardAssignment {
+    pub wildcards: u32,
+    // The card every wildcard ended up counting as
+    pub joined: CamelCard,
+    // What the hand would have been with the wildcards as plain cards
+    pub natural_type: HandType,
+}
+
+// A single line saying what a hand is and, if it held any wildcards, what they were counted as
+pub fn explain(hand: &CamelCardsHand) -> String {
+    let cards = hand.cards.iter().map(|card| card.0).collect::<String>();
+
+    match &hand.wildcard_assignment {
+        Some(assignment) => format!(
+            "{}: {}, {} wildcard{} counted as {} make{} it {}",
+            cards,
+            assignment.natural_type,
+            assignment.wildcards,
+            if assignment.wildcards == 1 { "" } else { "s" },
+            assignment.joined.0,
+            if assignment.wildcards == 1 { "s" } else { "" },
+            hand.hand_type,
+        ),
+        None => format!("{}: {}", cards, hand.hand_type),
+    }
+}
+
+// How many hands of every type there are, strongest type first
+pub fn distribution(hands: &[CamelCardsHand]) -> Vec<(HandType, usize)> {
+    HandType::ALL.iter()
+        .map(|hand_type| (*hand_type, hands.iter().filter(|hand| hand.hand_type == *hand_type).count()))
+        .collect()
+}
+
+pub fn report(hands: &[CamelCardsHand]) -> String {
+    let mut lines = vec![format!("{:<16} {:>6} {:>8} {:>10}", "hand type", "hands", "share", "wildcards")];
+
+    for (hand_type, count) in distribution(hands) {
+        let share = if hands.is_empty() { 0.0 } else { count as f64 * 100.0 / hands.len() as f64 };
+        let with_wildcards = hands.iter()
+            .filter(|hand| hand.hand_type == hand_type && hand.wildcard_assignment.is_some())
+            .count();
+
+        lines.push(format!("{:<16} {:>6} {:>7.2}% {:>10}", hand_type.name(), count, share, with_wildcards));
+    }
+
+    lines.join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use super::super::CamelCardsGame;
+    use super::super::rules::RuleSet;
+
+    fn example_hands(rules: RuleSet) -> Vec<CamelCardsHand> {
+        CamelCardsGame::parse(include_str!("example.txt"), &Rc::new(rules)).unwrap().1.hands
+    }
+
+    #[test]
+    fn test_from_counts() {
+        let cases = [
+            (vec![5], HandType::FiveOfAKind),
+            (vec![1, 4], HandType::FourOfAKind),
+            (vec![2, 3], HandType::FullHouse),
+            (vec![3, 1, 1], HandType::ThreeOfAKind),
+            (vec![2, 1, 2], HandType::TwoPair),
+            (vec![1, 2, 1, 1], HandType::OnePair),
+            (vec![1, 1, 1, 1, 1], HandType::HighCard),
+            (vec![7], HandType::FiveOfAKind),
+            (vec![3, 3, 1], HandType::FullHouse),
+            (vec![2, 2, 2, 1], HandType::TwoPair),
+            (vec![], HandType::HighCard),
+        ];
+
+        for (counts, hand_type) in cases {
+            assert_eq!(HandType::from_counts(counts.clone().into_iter()), hand_type, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let explanations = example_hands(RuleSet::jokers_wildcard()).iter().map(explain).collect::<Vec<_>>();
+
+        assert_eq!(explanations[0], "2345A: high card");
+        assert_eq!(explanations[10], "KTJJT: two pair, 2 wildcards counted as T make it four of a kind");
+        assert_eq!(explanations[5], "2345J: high card, 1 wildcard counted as 5 makes it one pair");
+        assert_eq!(explanations[12], "JJJJJ: five of a kind, 5 wildcards counted as A make it five of a kind");
+
+        let explanations = example_hands(RuleSet::normal()).iter().map(explain).collect::<Vec<_>>();
+        assert_eq!(explanations[10], "KTJJT: two pair");
+    }
+
+    #[test]
+    fn test_report() {
+        let hands = example_hands(RuleSet::jokers_wildcard());
+
+        assert_eq!(distribution(&hands), vec![
+            (HandType::FiveOfAKind, 6),
+            (HandType::FourOfAKind, 4),
+            (HandType::FullHouse, 2),
+            (HandType::ThreeOfAKind, 2),
+            (HandType::TwoPair, 1),
+            (HandType::OnePair, 3),
+            (HandType::HighCard, 1),
+        ]);
+
+        let report = report(&hands);
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[1].starts_with("five of a kind"));
+        assert!(lines[1].ends_with("31.58%          5"));
+    }
+}
@@ -1,4 +1,7 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
 use nom::{
     multi::{
         count,
@@ -12,6 +15,14 @@ use nom::{
     }
 };
 
+mod hand_type;
+mod odds;
+mod rules;
+mod tournament;
+
+use hand_type::{HandType, WildcardAssignment};
+use rules::{RuleSet, TieBreak};
+
 const CAMEL_CARDS_NORMAL: &str = "AKQJT98765432";
 const CAMEL_CARDS_JOKER_WILDCARD: &str = "AKQT98765432J";
 
@@ -21,153 +32,82 @@ struct CamelCard(char);
 
 #[derive(Debug, Clone, Eq)]
 struct CamelCardsHand {
-    hit_counter: HashMap<CamelCard, u32>,
     cards: Vec<CamelCard>,
     bid: u32,
-    jokers_wildcard: bool,
+    rules: Rc<RuleSet>,
+    hand_type: HandType,
+    wildcard_assignment: Option<WildcardAssignment>,
 }
 
 impl CamelCardsHand {
-    fn new(cards: Vec<CamelCard>, bid: u32, jokers_wildcard: bool) -> CamelCardsHand {
+    fn new(cards: Vec<CamelCard>, bid: u32, rules: Rc<RuleSet>) -> CamelCardsHand {
         let mut hit_counter: HashMap<CamelCard, u32> = HashMap::new();
         for card in &cards {
             let count = hit_counter.entry(*card).or_insert(0);
             *count += 1;
         }
 
-        // We want the highest hit count, but we don't want to count the joker as a hit
-        let highest_hit_entry = hit_counter.iter()
-            .filter(|(card, _)| **card != CamelCard('J'))
-            .max_by_key(|(_, count)| *count);
+        let natural_type = HandType::from_counts(hit_counter.values().copied());
+
+        // Take the wildcards out of the count, they join whichever card has the highest hit count
+        let wildcard_hit_count = cards.iter().filter(|card| rules.is_wildcard(**card)).count() as u32;
+        hit_counter.retain(|card, _| !rules.is_wildcard(*card));
 
-        if jokers_wildcard && highest_hit_entry.is_some() {
-            let highest_hit_entry = highest_hit_entry.unwrap();
-            // Find the highest hit count and add the joker count to it
-            let joker_hit_count = hit_counter.get(&CamelCard('J')).unwrap_or(&0).clone();
+        let mut wildcard_assignment = None;
+        if wildcard_hit_count > 0 {
+            // Ties between counts go to the strongest card, so the explanation doesn't depend on the hash order
+            let highest_hit_card = hit_counter.iter()
+                .max_by_key(|(card, count)| (**count, Reverse(rules.strength(**card))))
+                .map(|(card, _)| *card)
+                // A hand of only wildcards makes them all the strongest card
+                .unwrap_or(CamelCard(rules.order[0]));
 
-            hit_counter
-                .entry(*highest_hit_entry.0)
-                .and_modify(|count| *count += joker_hit_count);
+            *hit_counter.entry(highest_hit_card).or_insert(0) += wildcard_hit_count;
 
-            // Remove the joker from the cards list
-            hit_counter.remove(&CamelCard('J'));
+            wildcard_assignment = Some(WildcardAssignment {
+                wildcards: wildcard_hit_count,
+                joined: highest_hit_card,
+                natural_type,
+            });
         }
 
         CamelCardsHand {
-            hit_counter,
+            hand_type: HandType::from_counts(hit_counter.values().copied()),
             cards,
             bid,
-            jokers_wildcard,
+            rules,
+            wildcard_assignment,
         }
     }
 
-    fn parse(input: &str, jokers_wildcard: bool) -> nom::IResult<&str, CamelCardsHand> {
-        let card_tag = one_of(CAMEL_CARDS_NORMAL);
-        let (input, cards) = count(card_tag, 5)(input)?;
+    fn parse<'a>(input: &'a str, rules: &Rc<RuleSet>) -> nom::IResult<&'a str, CamelCardsHand> {
+        let alphabet = rules.alphabet();
+        let card_tag = one_of(alphabet.as_str());
+        let (input, cards) = count(card_tag, rules.hand_size)(input)?;
         let (input, _) = char(' ')(input)?;
         let (input, bid) = digit1(input)?;
 
         let camel_cards = cards.iter().map(|c| CamelCard(*c)).collect();
 
-        Ok((input, CamelCardsHand::new(camel_cards, bid.parse().unwrap(), jokers_wildcard)))
+        Ok((input, CamelCardsHand::new(camel_cards, bid.parse().unwrap(), Rc::clone(rules))))
     }
 
-    fn has_x_of_a_kind(&self, x: u32) -> bool {
-        for (_, count) in &self.hit_counter {
-            if *count == x {
-                return true;
-            }
-        }
-        false
-    }
+    // The strength of every card in the order the tie break compares them, lower is stronger
+    fn tie_break_strengths(&self) -> Vec<usize> {
+        let mut strengths = self.cards.iter().map(|card| self.rules.strength(*card)).collect::<Vec<_>>();
 
-    fn has_x_pair(&self, x: u32) -> bool {
-        let mut pair_count = 0;
-        for (_, count) in &self.hit_counter {
-            if *count == 2 {
-                pair_count += 1;
-            }
+        if self.rules.tie_break == TieBreak::HighestCard {
+            strengths.sort();
         }
-        pair_count == x
-    }
-
-    fn has_five_of_a_kind(&self) -> bool {
-        self.has_x_of_a_kind(5)
-    }
-
-    fn has_four_of_a_kind(&self) -> bool {
-        self.has_x_of_a_kind(4)
-    }
-
-    fn has_three_of_a_kind(&self) -> bool {
-        self.has_x_of_a_kind(3)
-    }
-
-    fn has_two_of_a_kind(&self) -> bool {
-        self.has_x_of_a_kind(2)
-    }
-
-    fn has_full_house(&self) -> bool {
-        self.has_three_of_a_kind() && self.has_two_of_a_kind()
-    }
-
-    fn has_two_pair(&self) -> bool {
-        self.has_x_pair(2)
-    }
 
-    fn has_one_pair(&self) -> bool {
-        self.has_x_pair(1)
+        strengths
     }
-
-    fn get_ranking(&self) -> u32 {
-        if self.has_five_of_a_kind() {
-            1
-        } else if self.has_four_of_a_kind() {
-            2
-        } else if self.has_full_house() {
-            3
-        } else if self.has_three_of_a_kind() {
-            4
-        } else if self.has_two_pair() {
-            5
-        } else if self.has_one_pair() {
-            6
-        } else {
-            7
-        }
-    }
-
 }
 
 impl Ord for CamelCardsHand {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let self_ranking = self.get_ranking();
-        let other_ranking = other.get_ranking();
-        if self_ranking == other_ranking {
-            let a_cards = &self.cards;
-            let b_cards = &other.cards;
-
-            for i in 0..a_cards.len() {
-                let a_card = a_cards[i];
-                let b_card = b_cards[i];
-                if a_card != b_card {
-                    let comparison_list = if self.jokers_wildcard {
-                        CAMEL_CARDS_JOKER_WILDCARD
-                    } else {
-                        CAMEL_CARDS_NORMAL
-                    };
-
-                    let a_index = comparison_list.find(a_card.0).unwrap();
-                    let b_index = comparison_list.find(b_card.0).unwrap();
-
-                    return a_index.cmp(&b_index);
-                }
-            }
-
-            std::cmp::Ordering::Equal
-        } else {
-            self_ranking.cmp(&other_ranking)
-        }
+        self.hand_type.cmp(&other.hand_type)
+            .then_with(|| self.tie_break_strengths().cmp(&other.tie_break_strengths()))
     }
 }
 
@@ -189,8 +129,8 @@ struct CamelCardsGame {
 }
 
 impl CamelCardsGame {
-    fn parse(input: &str, jokers_wildcard: bool) -> nom::IResult<&str, CamelCardsGame> {
-        let (input, hands) = separated_list1(line_ending, |input| CamelCardsHand::parse(input, jokers_wildcard))(input)?;
+    fn parse<'a>(input: &'a str, rules: &Rc<RuleSet>) -> nom::IResult<&'a str, CamelCardsGame> {
+        let (input, hands) = separated_list1(line_ending, |input| CamelCardsHand::parse(input, rules))(input)?;
         Ok((input, CamelCardsGame { hands }))
     }
 
@@ -200,44 +140,150 @@ impl CamelCardsGame {
         sorted_hands
     }
 
-    fn calculate_sum(&self) -> u32 {
-        let sorted_hands = self.get_sorted_hands();
-
-        for hand in &sorted_hands {
-            let cards_string = hand.cards.iter().map(|card| card.0).collect::<String>();
-            println!("{:?} {:?}", cards_string, hand.bid);
-        }
-
-        let sum = sorted_hands.iter()
-            .enumerate()
-            .map(|(i, hand)| {
-                let rank_from_top = sorted_hands.len() - i;
-                hand.bid * rank_from_top as u32
-            })
-            .sum::<u32>();
-
-        sum
+    fn calculate_sum(&self) -> Result<u64, &'static str> {
+        tournament::Tournament::run(self, tournament::TieRule::InputOrder)?.total_payout_u64()
     }
     
 }
 
-fn part1(input: &str) -> u32 {
-    let (input, game) = CamelCardsGame::parse(input, false).unwrap();
-    let sum = game.calculate_sum();
+fn part1(input: &str) -> u64 {
+    let (input, game) = CamelCardsGame::parse(input, &Rc::new(RuleSet::normal())).unwrap();
+    let sum = game.calculate_sum().unwrap();
 
     sum
 }
 
-fn part2(input: &str) -> u32 {
-    let (input, game) = CamelCardsGame::parse(input, true).unwrap();
-    let sum = game.calculate_sum();
+fn part2(input: &str) -> u64 {
+    let (input, game) = CamelCardsGame::parse(input, &Rc::new(RuleSet::jokers_wildcard())).unwrap();
+    let sum = game.calculate_sum().unwrap();
 
     sum
 }
 
+fn play_with_rules(input: &str, rules: RuleSet) -> u64 {
+    let (_, game) = CamelCardsGame::parse(input, &Rc::new(rules)).unwrap();
+
+    game.calculate_sum().unwrap()
+}
+
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("Usage: day7 [--rules <rule set file>] [--explain] [--types] [--odds <known cards> [--deck <card counts>] [--against <cards>] [--samples <count>] [--seed <seed>]] [--tournament <input|lowest|highest|reject>] [--versus <cards> <cards>]");
+    std::process::exit(1);
+}
+
 fn main() {
     let input = include_str!("input.txt");
 
+    let mut rules = None;
+    let mut explain_hands = false;
+    let mut show_types = false;
+    let mut known_cards = None;
+    let mut deck = None;
+    let mut opponent = None;
+    let mut samples = None;
+    let mut seed = 0;
+    let mut tie_rule = None;
+    let mut versus = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| exit_with_error("Missing argument value"));
+
+        match arg.as_str() {
+            "--rules" => {
+                let config = std::fs::read_to_string(value()).unwrap_or_else(|_| exit_with_error("Couldn't read the rule set file"));
+                rules = Some(RuleSet::parse(&config).unwrap_or_else(|error| exit_with_error(error)));
+            }
+            "--explain" => explain_hands = true,
+            "--types" => show_types = true,
+            "--odds" => known_cards = Some(value()),
+            "--deck" => deck = Some(value()),
+            "--against" => opponent = Some(value()),
+            "--samples" => samples = Some(value().parse::<u64>().unwrap_or_else(|_| exit_with_error("Invalid number of samples"))),
+            "--seed" => seed = value().parse::<u64>().unwrap_or_else(|_| exit_with_error("Invalid seed")),
+            "--tournament" => tie_rule = Some(tournament::TieRule::try_from(value().as_str()).unwrap_or_else(|error| exit_with_error(error))),
+            "--versus" => versus = Some((value(), value())),
+            _ => exit_with_error("Unknown argument"),
+        }
+    }
+
+    // day7 --odds <known cards> gives the chance of the hand finishing as every type. The rest of the hand
+    // comes from --deck, or from four of every card minus the ones already in sight, and is enumerated
+    // unless --samples asks for a Monte Carlo estimate. --against adds the odds of beating that hand.
+    if let Some(known_cards) = known_cards {
+        let rules = Rc::new(rules.unwrap_or_else(RuleSet::jokers_wildcard));
+        let known = known_cards.chars().map(CamelCard).collect::<Vec<_>>();
+
+        let opponent = opponent.map(|cards| {
+            CamelCardsHand::parse(&format!("{} 0", cards), &rules).unwrap_or_else(|_| exit_with_error("Invalid opponent hand")).1
+        });
+
+        let deck = match deck {
+            Some(deck) => odds::Deck::parse(&deck, &rules).unwrap_or_else(|error| exit_with_error(error)),
+            None => {
+                let mut deck = odds::Deck::full(&rules, 4);
+                let in_sight = known.iter().chain(opponent.iter().flat_map(|hand| hand.cards.iter())).copied().collect::<Vec<_>>();
+                deck.remove(&in_sight).unwrap_or_else(|error| exit_with_error(error));
+                deck
+            }
+        };
+
+        let method = match samples {
+            Some(samples) => odds::Method::MonteCarlo { samples, seed },
+            None => odds::Method::Exhaustive,
+        };
+
+        let odds = odds::hand_odds(&known, &deck, &rules, opponent.as_ref(), method).unwrap_or_else(|error| exit_with_error(error));
+        println!("{}", odds::report(&odds));
+        return;
+    }
+
+    // day7 --tournament <tie rule> prints the ranked table as CSV and the total payout, --versus <a> <b>
+    // says which of two hands wins and why
+    if tie_rule.is_some() || versus.is_some() {
+        let rules = rules.unwrap_or_else(RuleSet::jokers_wildcard);
+        let (_, game) = CamelCardsGame::parse(input, &Rc::new(rules)).unwrap();
+
+        if let Some(tie_rule) = tie_rule {
+            let tournament = tournament::Tournament::run(&game, tie_rule).unwrap_or_else(|error| exit_with_error(error));
+            let total = tournament.total_payout().unwrap_or_else(|error| exit_with_error(error));
+
+            print!("{}", tournament.table());
+            println!("Total payout: {}", total);
+        }
+        if let Some((a, b)) = versus {
+            match tournament::head_to_head(&game, &a, &b).unwrap_or_else(|error| exit_with_error(error)) {
+                tournament::HeadToHead::Wins { winner, loser, reason } => println!("{} beats {}: {}", winner, loser, reason),
+                tournament::HeadToHead::Tie => println!("{} and {} tie", a, b),
+            }
+        }
+        return;
+    }
+
+    // day7 --explain shows the type of every hand and what its wildcards were counted as, --types how
+    // many hands of each type there are. Both use the part 2 rules unless --rules says otherwise.
+    if explain_hands || show_types {
+        let rules = rules.unwrap_or_else(RuleSet::jokers_wildcard);
+        let (_, game) = CamelCardsGame::parse(input, &Rc::new(rules)).unwrap();
+
+        if explain_hands {
+            for hand in &game.hands {
+                println!("{}", hand_type::explain(hand));
+            }
+        }
+        if show_types {
+            println!("{}", hand_type::report(&game.hands));
+        }
+        return;
+    }
+
+    // day7 --rules <file> scores the hands with a variant of the game instead of the two parts
+    if let Some(rules) = rules {
+        println!("Sum: {}", play_with_rules(input, rules));
+        return;
+    }
+
     let part1_sum = part1(input);
     println!("Part 1 sum: {}", part1_sum);
 
@@ -273,4 +319,52 @@ mod tests {
 
         assert_eq!(ranking_sum, 6839);
     }
+
+    fn hand(cards: &str, rules: &Rc<RuleSet>) -> CamelCardsHand {
+        CamelCardsHand::parse(&format!("{} 1", cards), rules).unwrap().1
+    }
+
+    #[test]
+    fn test_rules_match_parts() {
+        let input = include_str!("example.txt");
+
+        assert_eq!(play_with_rules(input, RuleSet::parse("order = AKQJT98765432").unwrap()), 6592);
+        assert_eq!(play_with_rules(input, RuleSet::parse("order = AKQT98765432J\nwildcards = J").unwrap()), 6839);
+    }
+
+    #[test]
+    fn test_multiple_wildcards() {
+        let rules = Rc::new(RuleSet::parse("order = AKQT98765432JW\nwildcards = JW").unwrap());
+
+        assert_eq!(hand("JW234", &rules).hand_type, HandType::ThreeOfAKind);
+        assert_eq!(hand("JWJW2", &rules).hand_type, HandType::FiveOfAKind);
+        assert_eq!(hand("JWJWW", &rules).hand_type, HandType::FiveOfAKind);
+        // Wildcards still tie break as the weakest cards in the order
+        assert!(hand("2233J", &rules) < hand("2233W", &rules));
+    }
+
+    #[test]
+    fn test_larger_hands() {
+        let rules = Rc::new(RuleSet::parse("order = AKQJT98765432\nhand_size = 7").unwrap());
+
+        assert_eq!(hand("2222222", &rules).hand_type, HandType::FiveOfAKind);
+        assert_eq!(hand("222233A", &rules).hand_type, HandType::FourOfAKind);
+        // Extra pairs or a second triple don't push a hand out of its category
+        assert_eq!(hand("2223334", &rules).hand_type, HandType::FullHouse);
+        assert_eq!(hand("2233445", &rules).hand_type, HandType::TwoPair);
+        assert_eq!(hand("223456A", &rules).hand_type, HandType::OnePair);
+        assert!(hand("2233445", &rules) < hand("AA34567", &rules));
+        assert!(CamelCardsHand::parse("22222 1", &rules).is_err());
+    }
+
+    #[test]
+    fn test_highest_card_tie_break() {
+        let dealt = Rc::new(RuleSet::normal());
+        let highest = Rc::new(RuleSet::parse("order = AKQJT98765432\ntie_break = highest").unwrap());
+
+        // Both are one pair, the first is dealt a stronger card but the second holds the ace
+        assert!(hand("KK234", &dealt) < hand("22A34", &dealt));
+        assert!(hand("KK234", &highest) > hand("22A34", &highest));
+        assert_eq!(hand("KK234", &highest).cmp(&hand("4K3K2", &highest)), std::cmp::Ordering::Equal);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,352 @@
+use std::rc::Rc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{CamelCard, CamelCardsHand};
+use super::hand_type::HandType;
+use super::rules::RuleSet;
+
+// Enumerating every draw grows with the number of different cards to the power of the cards drawn,
+// so give up past this many and let the caller sample instead
+const MAX_EXHAUSTIVE_DRAWS: u128 = 10_000_000;
+
+// The weights count every ordered way to deal from the deck, which can outgrow a u128 for huge decks
+const TOO_MANY_DEALS: &str = "Too many ways to deal from the deck to count, sample instead";
+
+// The cards that can still be drawn and how many of each are left
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deck {
+    counts: Vec<(CamelCard, u32)>,
+}
+
+impl Deck {
+    // copies of every card in the rule set
+    pub fn full(rules: &RuleSet, copies: u32) -> Deck {
+        Deck { counts: rules.order.iter().map(|card| (CamelCard(*card), copies)).collect() }
+    }
+
+    // A deck is written as every card followed by how many of it are left, e.g. "A4 K3 J2"
+    pub fn parse(text: &str, rules: &RuleSet) -> Result<Deck, &'static str> {
+        let mut counts: Vec<(CamelCard, u32)> = Vec::new();
+
+        for entry in text.split_whitespace() {
+            let mut chars = entry.chars();
+            let card = CamelCard(chars.next().unwrap());
+            let count = chars.as_str().parse::<u32>().map_err(|_| "Invalid card count in deck")?;
+
+            if !rules.order.contains(&card.0) {
+                return Err("Deck contains a card that isn't part of the game");
+            }
+            if counts.iter().any(|(other, _)| *other == card) {
+                return Err("Deck lists a card twice");
+            }
+
+            counts.push((card, count));
+        }
+
+        Ok(Deck { counts })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.counts.iter().map(|(_, count)| *count as u64).sum()
+    }
+
+    pub fn remove(&mut self, cards: &[CamelCard]) -> Result<(), &'static str> {
+        for card in cards {
+            let entry = self.counts.iter_mut()
+                .find(|(other, count)| other == card && *count > 0)
+                .ok_or("Card isn't left in the deck")?;
+
+            entry.1 -= 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    // Walk every possible draw, weighted by how many ways the deck can deal it
+    Exhaustive,
+    // Deal samples random draws, seeded so the same query gives the same answer
+    MonteCarlo { samples: u64, seed: u64 },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Odds {
+    // Every hand type with its probability, strongest first
+    pub hand_types: Vec<(HandType, f64)>,
+    // Against the opponent, if there is one. Whatever is left over is a loss.
+    pub win: Option<f64>,
+    pub tie: Option<f64>,
+}
+
+// Weighted counts of the finished hands seen so far
+struct Tally<'a> {
+    rules: &'a Rc<RuleSet>,
+    opponent: Option<&'a CamelCardsHand>,
+    hand_types: [u128; 7],
+    wins: u128,
+    ties: u128,
+    total: u128,
+}
+
+impl<'a> Tally<'a> {
+    fn new(rules: &'a Rc<RuleSet>, opponent: Option<&'a CamelCardsHand>) -> Tally<'a> {
+        Tally { rules, opponent, hand_types: [0; 7], wins: 0, ties: 0, total: 0 }
+    }
+
+    fn add(&mut self, cards: &[CamelCard], weight: u128) -> Result<(), &'static str> {
+        let hand = CamelCardsHand::new(cards.to_vec(), 0, Rc::clone(self.rules));
+        let add = |total: &mut u128| -> Result<(), &'static str> {
+            *total = total.checked_add(weight).ok_or(TOO_MANY_DEALS)?;
+            Ok(())
+        };
+
+        add(&mut self.hand_types[hand.hand_type as usize])?;
+        add(&mut self.total)?;
+
+        // Hands sort strongest first, so the smaller hand is the winner
+        match self.opponent.map(|opponent| hand.cmp(opponent)) {
+            Some(std::cmp::Ordering::Less) => add(&mut self.wins),
+            Some(std::cmp::Ordering::Equal) => add(&mut self.ties),
+            _ => Ok(()),
+        }
+    }
+
+    fn odds(&self) -> Odds {
+        let probability = |weight: u128| weight as f64 / self.total as f64;
+
+        Odds {
+            hand_types: HandType::ALL.iter().map(|hand_type| (*hand_type, probability(self.hand_types[*hand_type as usize]))).collect(),
+            win: self.opponent.map(|_| probability(self.wins)),
+            tie: self.opponent.map(|_| probability(self.ties)),
+        }
+    }
+}
+
+// Deals the remaining cards one at a time. Every card kind is a branch, weighted by how many copies
+// of it are left, so the weights add up to the number of ordered ways to deal from the deck.
+fn enumerate(cards: &mut Vec<CamelCard>, counts: &mut [(CamelCard, u32)], to_draw: usize, weight: u128, tally: &mut Tally) -> Result<(), &'static str> {
+    if to_draw == 0 {
+        return tally.add(cards, weight);
+    }
+
+    for index in 0..counts.len() {
+        let (card, count) = counts[index];
+        if count == 0 {
+            continue;
+        }
+
+        let weight = weight.checked_mul(count as u128).ok_or(TOO_MANY_DEALS)?;
+
+        counts[index].1 -= 1;
+        cards.push(card);
+        enumerate(cards, counts, to_draw - 1, weight, tally)?;
+        cards.pop();
+        counts[index].1 += 1;
+    }
+
+    Ok(())
+}
+
+fn sample(cards: &mut Vec<CamelCard>, deck: &Deck, to_draw: usize, samples: u64, seed: u64, tally: &mut Tally) -> Result<(), &'static str> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let known = cards.len();
+
+    for _ in 0..samples {
+        let mut counts = deck.counts.clone();
+        let mut left = deck.size();
+
+        for _ in 0..to_draw {
+            let mut pick = rng.gen_range(0..left);
+            let entry = counts.iter_mut()
+                .find(|(_, count)| {
+                    if pick < *count as u64 {
+                        return true;
+                    }
+                    pick -= *count as u64;
+                    false
+                })
+                .unwrap();
+
+            entry.1 -= 1;
+            left -= 1;
+            cards.push(entry.0);
+        }
+
+        tally.add(cards, 1)?;
+        cards.truncate(known);
+    }
+
+    Ok(())
+}
+
+// The chance of the known cards finishing as every hand type once the rest of the hand is dealt from
+// the deck, and of the finished hand beating the opponent. The known cards are the first ones dealt,
+// which matters for tie breaks in dealt order.
+pub fn hand_odds(known: &[CamelCard], deck: &Deck, rules: &Rc<RuleSet>, opponent: Option<&CamelCardsHand>, method: Method) -> Result<Odds, &'static str> {
+    if known.len() > rules.hand_size {
+        return Err("More known cards than fit in a hand");
+    }
+    if known.iter().any(|card| !rules.order.contains(&card.0)) {
+        return Err("Known card isn't part of the game");
+    }
+
+    let to_draw = rules.hand_size - known.len();
+    if deck.size() < to_draw as u64 {
+        return Err("Not enough cards left in the deck");
+    }
+
+    let mut tally = Tally::new(rules, opponent);
+    let mut cards = known.to_vec();
+
+    match method {
+        Method::Exhaustive => {
+            let kinds = deck.counts.iter().filter(|(_, count)| *count > 0).count() as u128;
+            if kinds.checked_pow(to_draw as u32).is_none_or(|draws| draws > MAX_EXHAUSTIVE_DRAWS) {
+                return Err("Too many draws to enumerate, sample instead");
+            }
+
+            let mut counts = deck.counts.clone();
+            enumerate(&mut cards, &mut counts, to_draw, 1, &mut tally)?;
+        }
+        Method::MonteCarlo { samples, seed } => {
+            if samples == 0 {
+                return Err("Need at least one sample");
+            }
+
+            sample(&mut cards, deck, to_draw, samples, seed, &mut tally)?;
+        }
+    }
+
+    Ok(tally.odds())
+}
+
+pub fn report(odds: &Odds) -> String {
+    let mut lines = odds.hand_types.iter()
+        .map(|(hand_type, probability)| format!("{:<16} {:>7.2}%", hand_type.name(), probability * 100.0))
+        .collect::<Vec<_>>();
+
+    if let (Some(win), Some(tie)) = (odds.win, odds.tie) {
+        lines.push(format!("Beats the opponent {:.2}% of the time, ties {:.2}%, loses {:.2}%", win * 100.0, tie * 100.0, (1.0 - win - tie).max(0.0) * 100.0));
+    }
+
+    lines.join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(text: &str) -> Vec<CamelCard> {
+        text.chars().map(CamelCard).collect()
+    }
+
+    fn probability(odds: &Odds, hand_type: HandType) -> f64 {
+        odds.hand_types.iter().find(|(other, _)| *other == hand_type).unwrap().1
+    }
+
+    #[test]
+    fn test_deck() {
+        let rules = RuleSet::normal();
+        let mut deck = Deck::parse("A4 K3 J2", &rules).unwrap();
+
+        assert_eq!(deck.size(), 9);
+        deck.remove(&cards("AAJ")).unwrap();
+        assert_eq!(deck, Deck::parse("A2 K3 J1", &rules).unwrap());
+        assert!(deck.remove(&cards("JJ")).is_err());
+
+        assert_eq!(Deck::full(&rules, 4).size(), 52);
+        assert!(Deck::parse("A4 A2", &rules).is_err());
+        assert!(Deck::parse("X4", &rules).is_err());
+        assert!(Deck::parse("A", &rules).is_err());
+    }
+
+    #[test]
+    fn test_exhaustive_odds() {
+        let rules = Rc::new(RuleSet::normal());
+
+        // One card left to draw out of two aces and two kings
+        let deck = Deck::parse("A2 K2", &rules).unwrap();
+        let odds = hand_odds(&cards("AAKK"), &deck, &rules, None, Method::Exhaustive).unwrap();
+
+        assert_eq!(probability(&odds, HandType::FullHouse), 1.0);
+        assert_eq!(odds.win, None);
+
+        // Two cards out of A, K and K: AK and KA can each be dealt two ways and stay one pair, while
+        // the two kings can also be dealt two ways and make two pair
+        let deck = Deck::parse("A1 K2", &rules).unwrap();
+        let odds = hand_odds(&cards("QQ2"), &deck, &rules, None, Method::Exhaustive).unwrap();
+
+        assert!((probability(&odds, HandType::OnePair) - 2.0 / 3.0).abs() < 1e-12);
+        assert!((probability(&odds, HandType::TwoPair) - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(probability(&odds, HandType::FullHouse), 0.0);
+    }
+
+    #[test]
+    fn test_joker_odds() {
+        let rules = Rc::new(RuleSet::jokers_wildcard());
+
+        // Drawing the joker turns three of a kind into four of a kind
+        let deck = Deck::parse("J1 21", &rules).unwrap();
+        let odds = hand_odds(&cards("AAA3"), &deck, &rules, None, Method::Exhaustive).unwrap();
+
+        assert_eq!(probability(&odds, HandType::FourOfAKind), 0.5);
+        assert_eq!(probability(&odds, HandType::ThreeOfAKind), 0.5);
+    }
+
+    #[test]
+    fn test_odds_against_opponent() {
+        let rules = Rc::new(RuleSet::normal());
+        let opponent = CamelCardsHand::parse("KKQQ5 1", &rules).unwrap().1;
+
+        // Both are two pair, so the last card dealt decides: the ace wins, the 5 ties and the 2 loses
+        let deck = Deck::parse("A1 51 21", &rules).unwrap();
+        let odds = hand_odds(&cards("KKQQ"), &deck, &rules, Some(&opponent), Method::Exhaustive).unwrap();
+
+        assert!((odds.win.unwrap() - 1.0 / 3.0).abs() < 1e-12);
+        assert!((odds.tie.unwrap() - 1.0 / 3.0).abs() < 1e-12);
+        assert!(report(&odds).ends_with("Beats the opponent 33.33% of the time, ties 33.33%, loses 33.33%"));
+    }
+
+    #[test]
+    fn test_monte_carlo_matches_exhaustive() {
+        let rules = Rc::new(RuleSet::jokers_wildcard());
+        let opponent = CamelCardsHand::parse("T55J5 1", &rules).unwrap().1;
+        let mut deck = Deck::full(&rules, 4);
+        deck.remove(&cards("KT")).unwrap();
+
+        let exact = hand_odds(&cards("KT"), &deck, &rules, Some(&opponent), Method::Exhaustive).unwrap();
+        let sampled = hand_odds(&cards("KT"), &deck, &rules, Some(&opponent), Method::MonteCarlo { samples: 50_000, seed: 7 }).unwrap();
+
+        for ((hand_type, expected), (_, actual)) in exact.hand_types.iter().zip(sampled.hand_types.iter()) {
+            assert!((expected - actual).abs() < 0.01, "{} {} {}", hand_type, expected, actual);
+        }
+        assert!((exact.win.unwrap() - sampled.win.unwrap()).abs() < 0.01);
+
+        let total = exact.hand_types.iter().map(|(_, probability)| probability).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_odds_errors() {
+        let rules = Rc::new(RuleSet::normal());
+        let deck = Deck::full(&rules, 4);
+
+        assert!(hand_odds(&cards("AAAAAA"), &deck, &rules, None, Method::Exhaustive).is_err());
+        assert!(hand_odds(&cards("AAX"), &deck, &rules, None, Method::Exhaustive).is_err());
+        assert!(hand_odds(&cards("AAA"), &Deck::parse("K1", &rules).unwrap(), &rules, None, Method::Exhaustive).is_err());
+        assert!(hand_odds(&cards("AAA"), &deck, &rules, None, Method::MonteCarlo { samples: 0, seed: 0 }).is_err());
+
+        let large = Rc::new(RuleSet::parse("order = AKQJT98765432\nhand_size = 9").unwrap());
+        assert!(hand_odds(&[], &deck, &large, None, Method::Exhaustive).is_err());
+
+        // (8 * 10^9)^5 ways to deal a hand don't fit in a u128, but sampling still works
+        let huge = Deck::parse("A4000000000 K4000000000", &rules).unwrap();
+        assert_eq!(hand_odds(&[], &huge, &rules, None, Method::Exhaustive), Err(TOO_MANY_DEALS));
+        assert!(hand_odds(&[], &huge, &rules, None, Method::MonteCarlo { samples: 100, seed: 0 }).is_ok());
+    }
+}
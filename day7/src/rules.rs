@@ -0,0 +1,164 @@
+use std::convert::TryFrom;
+
+use super::{CamelCard, CAMEL_CARDS_JOKER_WILDCARD, CAMEL_CARDS_NORMAL};
+
+// How two hands of the same type are told apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    // Compare the cards one by one in the order they were dealt, like in camel cards
+    DealtOrder,
+    // Compare the strongest card of each hand first, then the next strongest and so on, like in poker
+    HighestCard,
+}
+
+impl TryFrom<&str> for TieBreak {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "dealt" => Ok(TieBreak::DealtOrder),
+            "highest" => Ok(TieBreak::HighestCard),
+            _ => Err("Unknown tie break, expected dealt or highest"),
+        }
+    }
+}
+
+// Everything that changes between variants of the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    // Every card in the game, strongest first
+    pub order: Vec<char>,
+    pub hand_size: usize,
+    // Cards that count as whichever other card makes the hand strongest
+    pub wildcards: Vec<char>,
+    pub tie_break: TieBreak,
+}
+
+impl RuleSet {
+    pub fn normal() -> RuleSet {
+        RuleSet {
+            order: CAMEL_CARDS_NORMAL.chars().collect(),
+            hand_size: 5,
+            wildcards: Vec::new(),
+            tie_break: TieBreak::DealtOrder,
+        }
+    }
+
+    pub fn jokers_wildcard() -> RuleSet {
+        RuleSet {
+            order: CAMEL_CARDS_JOKER_WILDCARD.chars().collect(),
+            hand_size: 5,
+            wildcards: vec!['J'],
+            tie_break: TieBreak::DealtOrder,
+        }
+    }
+
+    pub fn alphabet(&self) -> String {
+        self.order.iter().collect()
+    }
+
+    // Lower is stronger, cards outside of the game are weaker than any other card
+    pub fn strength(&self, card: CamelCard) -> usize {
+        self.order.iter().position(|c| *c == card.0).unwrap_or(self.order.len())
+    }
+
+    pub fn is_wildcard(&self, card: CamelCard) -> bool {
+        self.wildcards.contains(&card.0)
+    }
+
+    // Rule sets are written one setting per line, with # starting a comment:
+    //
+    //   order = AKQT98765432J
+    //   hand_size = 5
+    //   wildcards = J
+    //   tie_break = dealt
+    //
+    // Only the order is required, the rest default to the rules of part 1.
+    pub fn parse(config: &str) -> Result<RuleSet, &'static str> {
+        let mut order = None;
+        let mut rules = RuleSet::normal();
+
+        for line in config.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or("Expected a key = value line")?;
+            let value = value.trim();
+
+            match key.trim() {
+                "order" => order = Some(value.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>()),
+                "hand_size" => rules.hand_size = value.parse().map_err(|_| "Invalid hand size")?,
+                "wildcards" => rules.wildcards = value.chars().filter(|c| !c.is_whitespace()).collect(),
+                "tie_break" => rules.tie_break = TieBreak::try_from(value)?,
+                _ => return Err("Unknown rule set key"),
+            }
+        }
+
+        rules.order = order.ok_or("Rule set is missing the card order")?;
+        rules.validate()?;
+
+        Ok(rules)
+    }
+
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.order.is_empty() {
+            return Err("Card order is empty");
+        }
+
+        let mut unique = self.order.clone();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != self.order.len() {
+            return Err("Card order contains a card twice");
+        }
+
+        if self.hand_size == 0 {
+            return Err("Hand size must be at least 1");
+        }
+
+        if self.wildcards.iter().any(|c| !self.order.contains(c)) {
+            return Err("Wildcard isn't part of the card order");
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule_set() {
+        let rules = RuleSet::parse("# part 2\norder = AKQT98765432J\nwildcards = J # jokers\n").unwrap();
+        assert_eq!(rules, RuleSet::jokers_wildcard());
+
+        let rules = RuleSet::parse("order = 321\nhand_size = 3\nwildcards = 1 2\ntie_break = highest").unwrap();
+        assert_eq!(rules, RuleSet { order: vec!['3', '2', '1'], hand_size: 3, wildcards: vec!['1', '2'], tie_break: TieBreak::HighestCard });
+    }
+
+    #[test]
+    fn test_parse_rule_set_errors() {
+        assert_eq!(RuleSet::parse("hand_size = 5"), Err("Rule set is missing the card order"));
+        assert_eq!(RuleSet::parse("order = AKA"), Err("Card order contains a card twice"));
+        assert_eq!(RuleSet::parse("order = AK\nwildcards = J"), Err("Wildcard isn't part of the card order"));
+        assert_eq!(RuleSet::parse("order = AK\nhand_size = 0"), Err("Hand size must be at least 1"));
+        assert_eq!(RuleSet::parse("order = AK\ncolour = red"), Err("Unknown rule set key"));
+        assert_eq!(RuleSet::parse("order AK"), Err("Expected a key = value line"));
+        assert!(RuleSet::parse("order = AK\ntie_break = random").is_err());
+    }
+
+    #[test]
+    fn test_strength() {
+        let rules = RuleSet::jokers_wildcard();
+
+        assert_eq!(rules.strength(CamelCard('A')), 0);
+        assert_eq!(rules.strength(CamelCard('J')), 12);
+        assert_eq!(rules.strength(CamelCard('Z')), 13);
+        assert!(rules.is_wildcard(CamelCard('J')));
+        assert!(!RuleSet::normal().is_wildcard(CamelCard('J')));
+    }
+}
@@ -0,0 +1,243 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use super::{CamelCardsGame, CamelCardsHand};
+use super::hand_type::HandType;
+
+// What to do with hands that compare equal, which under the dealt order tie break means the same cards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieRule {
+    // The hand listed first in the input takes the higher rank, like the sum has always been scored
+    InputOrder,
+    // Every tied hand gets the lowest rank of the group
+    Lowest,
+    // Every tied hand gets the highest rank of the group
+    Highest,
+    // Ties are an error
+    Reject,
+}
+
+impl TryFrom<&str> for TieRule {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "input" => Ok(TieRule::InputOrder),
+            "lowest" => Ok(TieRule::Lowest),
+            "highest" => Ok(TieRule::Highest),
+            "reject" => Ok(TieRule::Reject),
+            _ => Err("Unknown tie rule, expected input, lowest, highest or reject"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    // 1 is the weakest hand, so the strongest hand has the highest rank and the largest payout
+    pub rank: u64,
+    pub cards: String,
+    pub hand_type: HandType,
+    pub bid: u32,
+    pub payout: u128,
+    // Whether another hand compared equal to this one
+    pub tied: bool,
+}
+
+// Every hand of a game, strongest first
+#[derive(Debug, PartialEq)]
+pub struct Tournament {
+    pub standings: Vec<Standing>,
+}
+
+fn cards_string(hand: &CamelCardsHand) -> String {
+    hand.cards.iter().map(|card| card.0).collect()
+}
+
+impl Tournament {
+    pub fn run(game: &CamelCardsGame, tie_rule: TieRule) -> Result<Tournament, &'static str> {
+        // The sort is stable, so tied hands stay in input order
+        let sorted_hands = game.get_sorted_hands();
+        let hand_count = sorted_hands.len() as u64;
+        let mut standings = Vec::with_capacity(sorted_hands.len());
+
+        let mut group_start = 0;
+        while group_start < sorted_hands.len() {
+            let group_end = (group_start..sorted_hands.len())
+                .take_while(|index| sorted_hands[*index].cmp(&sorted_hands[group_start]) == Ordering::Equal)
+                .last()
+                .unwrap();
+            let tied = group_end > group_start;
+
+            if tied && tie_rule == TieRule::Reject {
+                return Err("Two hands are tied");
+            }
+
+            for (index, hand) in sorted_hands.iter().enumerate().take(group_end + 1).skip(group_start) {
+                let rank = match tie_rule {
+                    TieRule::InputOrder | TieRule::Reject => hand_count - index as u64,
+                    TieRule::Lowest => hand_count - group_end as u64,
+                    TieRule::Highest => hand_count - group_start as u64,
+                };
+
+                standings.push(Standing {
+                    rank,
+                    cards: cards_string(hand),
+                    hand_type: hand.hand_type,
+                    bid: hand.bid,
+                    // A u32 bid times a u64 rank always fits
+                    payout: hand.bid as u128 * rank as u128,
+                    tied,
+                });
+            }
+
+            group_start = group_end + 1;
+        }
+
+        Ok(Tournament { standings })
+    }
+
+    pub fn total_payout(&self) -> Result<u128, &'static str> {
+        self.standings.iter()
+            .try_fold(0u128, |total, standing| total.checked_add(standing.payout))
+            .ok_or("Total payout overflows u128")
+    }
+
+    pub fn total_payout_u64(&self) -> Result<u64, &'static str> {
+        self.standings.iter()
+            .try_fold(0u64, |total, standing| u64::try_from(standing.payout).ok().and_then(|payout| total.checked_add(payout)))
+            .ok_or("Total payout overflows u64")
+    }
+
+    // The standings as CSV, strongest hand first
+    pub fn table(&self) -> String {
+        let mut lines = vec!["rank,cards,hand_type,bid,payout,tied".to_string()];
+
+        for standing in &self.standings {
+            lines.push(format!("{},{},{},{},{},{}", standing.rank, standing.cards, standing.hand_type, standing.bid, standing.payout, standing.tied));
+        }
+
+        format!("{}\n", lines.join("\n"))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HeadToHead {
+    Wins { winner: String, loser: String, reason: String },
+    Tie,
+}
+
+// Compares the first hands in the game dealt a and b, explaining what decided it
+pub fn head_to_head(game: &CamelCardsGame, a: &str, b: &str) -> Result<HeadToHead, &'static str> {
+    let find = |cards: &str| game.hands.iter().find(|hand| cards_string(hand) == cards).ok_or("No hand with those cards in the game");
+    let (a, b) = (find(a)?, find(b)?);
+
+    let (winner, loser) = match a.cmp(b) {
+        Ordering::Less => (a, b),
+        Ordering::Greater => (b, a),
+        Ordering::Equal => return Ok(HeadToHead::Tie),
+    };
+
+    let reason = if winner.hand_type != loser.hand_type {
+        format!("{} against {}", winner.hand_type, loser.hand_type)
+    } else {
+        let (winner_strengths, loser_strengths) = (winner.tie_break_strengths(), loser.tie_break_strengths());
+        let position = winner_strengths.iter().zip(loser_strengths.iter()).position(|(w, l)| w != l).unwrap();
+        let card = |strength: usize| winner.rules.order[strength];
+
+        format!("both {}, {} beats {} at tie break card {}", winner.hand_type, card(winner_strengths[position]), card(loser_strengths[position]), position + 1)
+    };
+
+    Ok(HeadToHead::Wins { winner: cards_string(winner), loser: cards_string(loser), reason })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use super::super::rules::RuleSet;
+
+    fn game(input: &str, rules: RuleSet) -> CamelCardsGame {
+        CamelCardsGame::parse(input, &Rc::new(rules)).unwrap().1
+    }
+
+    #[test]
+    fn test_example_payouts() {
+        let input = include_str!("example.txt");
+
+        let tournament = Tournament::run(&game(input, RuleSet::normal()), TieRule::InputOrder).unwrap();
+        assert_eq!(tournament.total_payout_u64(), Ok(6592));
+        assert_eq!(tournament.total_payout(), Ok(6592));
+
+        let tournament = Tournament::run(&game(input, RuleSet::jokers_wildcard()), TieRule::Reject).unwrap();
+        assert_eq!(tournament.total_payout_u64(), Ok(6839));
+        assert_eq!(tournament.standings[0].rank, 19);
+        assert_eq!(tournament.standings[0].cards, "AAAAA");
+    }
+
+    #[test]
+    fn test_tie_rules() {
+        let input = "32T3K 1\nKK677 10\n32T3K 100\n2345A 1000";
+        let game = game(input, RuleSet::normal());
+
+        let ranks = |tie_rule| {
+            Tournament::run(&game, tie_rule).unwrap().standings.iter().map(|standing| (standing.bid, standing.rank)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(ranks(TieRule::InputOrder), vec![(10, 4), (1, 3), (100, 2), (1000, 1)]);
+        assert_eq!(ranks(TieRule::Lowest), vec![(10, 4), (1, 2), (100, 2), (1000, 1)]);
+        assert_eq!(ranks(TieRule::Highest), vec![(10, 4), (1, 3), (100, 3), (1000, 1)]);
+        assert_eq!(Tournament::run(&game, TieRule::Reject), Err("Two hands are tied"));
+
+        let tournament = Tournament::run(&game, TieRule::Lowest).unwrap();
+        assert_eq!(tournament.standings.iter().map(|standing| standing.tied).collect::<Vec<_>>(), vec![false, true, true, false]);
+        assert_eq!(tournament.total_payout(), Ok(40 + 2 + 200 + 1000));
+    }
+
+    #[test]
+    fn test_payout_overflow() {
+        let standing = Standing { rank: 1, cards: "AAAAA".to_string(), hand_type: HandType::FiveOfAKind, bid: 0, payout: u64::MAX as u128, tied: false };
+        let tournament = Tournament { standings: vec![standing.clone(), standing.clone()] };
+
+        assert_eq!(tournament.total_payout_u64(), Err("Total payout overflows u64"));
+        assert_eq!(tournament.total_payout(), Ok(2 * u64::MAX as u128));
+
+        let tournament = Tournament { standings: vec![Standing { payout: u128::MAX, ..standing.clone() }, standing] };
+        assert_eq!(tournament.total_payout(), Err("Total payout overflows u128"));
+    }
+
+    #[test]
+    fn test_head_to_head() {
+        let game = game(include_str!("example.txt"), RuleSet::jokers_wildcard());
+
+        assert_eq!(head_to_head(&game, "KK677", "KTJJT"), Ok(HeadToHead::Wins {
+            winner: "KTJJT".to_string(),
+            loser: "KK677".to_string(),
+            reason: "four of a kind against two pair".to_string(),
+        }));
+        assert_eq!(head_to_head(&game, "T55J5", "QQQJA"), Ok(HeadToHead::Wins {
+            winner: "QQQJA".to_string(),
+            loser: "T55J5".to_string(),
+            reason: "both four of a kind, Q beats T at tie break card 1".to_string(),
+        }));
+        assert_eq!(head_to_head(&game, "AAAAA", "AAAAA"), Ok(HeadToHead::Tie));
+        assert!(head_to_head(&game, "AAAAA", "KKKKK").is_err());
+    }
+
+    #[test]
+    fn test_table() {
+        let tournament = Tournament::run(&game("32T3K 765\nKK677 28", RuleSet::normal()), TieRule::InputOrder).unwrap();
+
+        assert_eq!(tournament.table(), "rank,cards,hand_type,bid,payout,tied
+2,KK677,two pair,28,56,false
+1,32T3K,one pair,765,765,false
+");
+    }
+
+    #[test]
+    fn test_tie_rule_from_str() {
+        assert_eq!(TieRule::try_from("input"), Ok(TieRule::InputOrder));
+        assert_eq!(TieRule::try_from("reject"), Ok(TieRule::Reject));
+        assert!(TieRule::try_from("coin flip").is_err());
+    }
+}